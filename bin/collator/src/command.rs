@@ -88,7 +88,8 @@ fn load_spec(id: &str) -> std::result::Result<Box<dyn sc_service::ChainSpec>, St
         "dev" => Box::new(development_config()),
         "astar-dev" => Box::new(chain_spec::astar::get_chain_spec()),
         "shibuya-dev" => Box::new(chain_spec::shibuya::get_chain_spec()),
-        "shiden-dev" => Box::new(chain_spec::shiden::get_chain_spec()),
+        "shiden-dev" => Box::new(chain_spec::shiden::get_chain_spec(None)),
+        "shiden-local" => Box::new(chain_spec::shiden::get_local_chain_spec()),
         "astar" => Box::new(chain_spec::AstarChainSpec::from_json_bytes(
             &include_bytes!("../res/astar.raw.json")[..],
         )?),
@@ -22,7 +22,7 @@ use local_runtime::{
     wasm_binary_unwrap, AccountId, AuraConfig, AuraId, BalancesConfig, BlockRewardConfig,
     CouncilConfig, DemocracyConfig, EVMConfig, GenesisConfig, GrandpaConfig, GrandpaId,
     Precompiles, Signature, SudoConfig, SystemConfig, TechnicalCommitteeConfig, TreasuryConfig,
-    VestingConfig,
+    VestingConfig, XvmConfig,
 };
 use sc_service::ChainType;
 use sp_core::{crypto::Ss58Codec, sr25519, Pair, Public};
@@ -177,6 +177,7 @@ fn testnet_genesis(
         },
         democracy: DemocracyConfig::default(),
         treasury: TreasuryConfig::default(),
+        xvm: XvmConfig { paused: false },
     }
 }
 
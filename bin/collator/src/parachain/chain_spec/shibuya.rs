@@ -24,7 +24,8 @@ use shibuya_runtime::{
     wasm_binary_unwrap, AccountId, AuraConfig, AuraId, Balance, BalancesConfig, BlockRewardConfig,
     CollatorSelectionConfig, CouncilConfig, DemocracyConfig, EVMChainIdConfig, EVMConfig,
     GenesisConfig, ParachainInfoConfig, Precompiles, SessionConfig, SessionKeys, Signature,
-    SudoConfig, SystemConfig, TechnicalCommitteeConfig, TreasuryConfig, VestingConfig, SBY,
+    SudoConfig, SystemConfig, TechnicalCommitteeConfig, TreasuryConfig, VestingConfig, XvmConfig,
+    SBY,
 };
 use sp_core::{sr25519, Pair, Public};
 
@@ -173,6 +174,7 @@ fn make_genesis(
         },
         democracy: DemocracyConfig::default(),
         treasury: TreasuryConfig::default(),
+        xvm: XvmConfig { paused: false },
     }
 }
 
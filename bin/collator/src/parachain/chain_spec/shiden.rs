@@ -20,11 +20,17 @@
 
 use cumulus_primitives_core::ParaId;
 use sc_service::ChainType;
+use sc_telemetry::TelemetryEndpoints;
 use shiden_runtime::{
-    wasm_binary_unwrap, AccountId, AuraId, Balance, BaseFeeConfig, BlockRewardConfig, EVMConfig,
-    ParachainInfoConfig, Precompiles, Signature, SystemConfig, SDN,
+    wasm_binary_unwrap, AccountId, AuraId, Balance, BaseFeeConfig, BlockNumber, BlockRewardConfig,
+    CollatorSelectionConfig, EVMConfig, ParachainInfoConfig, Precompiles, Signature, SystemConfig,
+    SDN,
+};
+use sp_core::{sr25519, Pair, Public, H160};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
 };
-use sp_core::{sr25519, Pair, Public};
 
 use sp_runtime::{
     traits::{IdentifyAccount, Verify},
@@ -34,15 +40,108 @@ use sp_runtime::{
 use super::{get_from_seed, Extensions};
 
 const PARA_ID: u32 = 2007;
+const RELAY_CHAIN: &str = "tokyo";
+
+const POLKADOT_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
+
+/// The simplest bytecode to revert without returning any data, pre-deployed under the
+/// precompiles so they can be called from within contracts.
+/// (PUSH1 0x00 PUSH1 0x00 REVERT)
+pub const PRECOMPILE_REVERT_BYTECODE: [u8; 5] = [0x60, 0x00, 0x60, 0x00, 0xFD];
 
 /// Specialized `ChainSpec` for Shiden Network.
 pub type ShidenChainSpec = sc_service::GenericChainSpec<shiden_runtime::GenesisConfig, Extensions>;
 
+/// Optional genesis settings of Shiden development chain specifications.
+#[derive(Clone, Default)]
+pub struct GenesisOptions {
+    /// Use zero candidacy bond and only the invulnerables as desired candidates,
+    /// for quick local devnets.
+    pub disable_collator_bonds: bool,
+    /// Block reward distribution between treasury, stakers, dApps and collators,
+    /// the development defaults are used if not set.
+    ///
+    /// Note the dApp staking era length is the runtime constant `BlockPerEra`, since
+    /// dApp staking has no genesis config.
+    pub reward_config: Option<pallet_block_reward::RewardDistributionConfig>,
+    /// Code to pre-deploy at specific addresses instead of `PRECOMPILE_REVERT_BYTECODE`.
+    /// Addresses which aren't precompiles are pre-deployed too.
+    pub precompile_stubs: BTreeMap<H160, Vec<u8>>,
+    /// Also pre-deploy the stub under the blacklisted precompiles, which are skipped by
+    /// default to keep consistent with the live network.
+    pub include_blacklisted_precompiles: bool,
+    /// EIP-1559 base fee per gas, `1_000_000_000` if not set.
+    pub base_fee_per_gas: Option<sp_core::U256>,
+    /// EIP-1559 base fee elasticity, zero if not set.
+    pub base_fee_elasticity: Option<sp_runtime::Permill>,
+    /// Vesting schedules as `(account, begin, length, liquid)`, refer to `pallet_vesting`.
+    ///
+    /// The endowment of the account above `liquid` is locked, so the account must be endowed
+    /// with at least `liquid`.
+    pub vesting: Vec<(AccountId, BlockNumber, BlockNumber, Balance)>,
+}
+
 /// Gen Shiden chain specification for given parachain id.
-pub fn get_chain_spec() -> ShidenChainSpec {
+///
+/// The node doesn't report to any telemetry server if `telemetry_endpoints` is `None`.
+pub fn get_chain_spec(telemetry_endpoints: Option<TelemetryEndpoints>) -> ShidenChainSpec {
+    build_chain_spec(
+        "Shiden Testnet",
+        "shiden",
+        ChainType::Development,
+        default_endowments(),
+        get_account_id_from_seed::<sr25519::Public>("Alice"),
+        default_authorities(),
+        GenesisOptions::default(),
+        telemetry_endpoints,
+        RELAY_CHAIN,
+        PARA_ID,
+    )
+    .expect("Default genesis options are valid; qed")
+}
+
+/// Gen Shiden chain specification for the given relay chain and parachain id, with the
+/// same genesis as `get_chain_spec(None)` otherwise.
+pub fn get_chain_spec_for(relay_chain: &str, para_id: u32) -> ShidenChainSpec {
+    build_chain_spec(
+        "Shiden Testnet",
+        "shiden",
+        ChainType::Development,
+        default_endowments(),
+        get_account_id_from_seed::<sr25519::Public>("Alice"),
+        default_authorities(),
+        GenesisOptions::default(),
+        None,
+        relay_chain,
+        para_id,
+    )
+    .expect("Default genesis options are valid; qed")
+}
+
+/// Polkadot telemetry server at verbosity level 0.
+pub fn polkadot_telemetry_endpoints() -> TelemetryEndpoints {
+    TelemetryEndpoints::new(vec![(POLKADOT_TELEMETRY_URL.to_string(), 0)])
+        .expect("Polkadot telemetry url is valid; qed")
+}
+
+/// Gen Shiden chain specification with the given genesis options.
+///
+/// Fails if the options are inconsistent, see `check_options`.
+pub fn get_chain_spec_with_options(options: GenesisOptions) -> Result<ShidenChainSpec, String> {
     // Alice as default
     let sudo_key = get_account_id_from_seed::<sr25519::Public>("Alice");
-    let endowned = vec![
+
+    get_chain_spec_with(
+        default_endowments(),
+        sudo_key,
+        default_authorities(),
+        options,
+    )
+}
+
+/// Alice and Bob as the default endowed accounts.
+fn default_endowments() -> Vec<(AccountId, Balance)> {
+    vec![
         (
             get_account_id_from_seed::<sr25519::Public>("Alice"),
             1_000_000_000 * SDN,
@@ -51,41 +150,140 @@ pub fn get_chain_spec() -> ShidenChainSpec {
             get_account_id_from_seed::<sr25519::Public>("Bob"),
             1_000_000_000 * SDN,
         ),
-    ];
+    ]
+}
+
+/// Gen Shiden chain specification with the given endowed accounts, sudo key,
+/// collator authorities and genesis options.
+///
+/// Fails if the options are inconsistent with the endowments, see `check_options`.
+pub fn get_chain_spec_with(
+    endowned: Vec<(AccountId, Balance)>,
+    sudo_key: AccountId,
+    authorities: Vec<(AccountId, AuraId)>,
+    options: GenesisOptions,
+) -> Result<ShidenChainSpec, String> {
+    build_chain_spec(
+        "Shiden Testnet",
+        "shiden",
+        ChainType::Development,
+        endowned,
+        sudo_key,
+        authorities,
+        options,
+        None,
+        RELAY_CHAIN,
+        PARA_ID,
+    )
+}
+
+/// Gen Shiden local testnet specification, with the same genesis as `get_chain_spec` but
+/// `ChainType::Local`, for multi-collator `--chain=shiden-local` runs.
+pub fn get_local_chain_spec() -> ShidenChainSpec {
+    build_chain_spec(
+        "Shiden Local Testnet",
+        "shiden-local",
+        ChainType::Local,
+        default_endowments(),
+        get_account_id_from_seed::<sr25519::Public>("Alice"),
+        default_authorities(),
+        GenesisOptions::default(),
+        None,
+        RELAY_CHAIN,
+        PARA_ID,
+    )
+    .expect("Default genesis options are valid; qed")
+}
+
+fn build_chain_spec(
+    name: &str,
+    id: &str,
+    chain_type: ChainType,
+    endowned: Vec<(AccountId, Balance)>,
+    sudo_key: AccountId,
+    authorities: Vec<(AccountId, AuraId)>,
+    options: GenesisOptions,
+    telemetry_endpoints: Option<TelemetryEndpoints>,
+    relay_chain: &str,
+    para_id: u32,
+) -> Result<ShidenChainSpec, String> {
+    check_options(&endowned, &options)?;
 
     let mut properties = serde_json::map::Map::new();
     properties.insert("tokenSymbol".into(), "SDN".into());
     properties.insert("tokenDecimals".into(), 18.into());
 
-    ShidenChainSpec::from_genesis(
-        "Shiden Testnet",
-        "shiden",
-        ChainType::Development,
-        move || make_genesis(endowned.clone(), sudo_key.clone(), PARA_ID.into()),
+    Ok(ShidenChainSpec::from_genesis(
+        name,
+        id,
+        chain_type,
+        move || {
+            make_genesis(
+                endowned.clone(),
+                sudo_key.clone(),
+                authorities.clone(),
+                para_id.into(),
+                &options,
+            )
+        },
         vec![],
-        None,
+        telemetry_endpoints,
         None,
         None,
         Some(properties),
         Extensions {
             bad_blocks: Default::default(),
-            relay_chain: "tokyo".into(),
-            para_id: PARA_ID,
+            relay_chain: relay_chain.into(),
+            para_id,
         },
+    ))
+}
+
+/// Gen Shiden chain specification with the endowments loaded from the JSON file at `path`.
+///
+/// The file must contain a list of `[account, balance]` entries, with SS58 encoded accounts.
+/// Alice is the sudo key, and the default authorities are used.
+pub fn get_chain_spec_from_json(path: &Path) -> Result<ShidenChainSpec, String> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read endowments file {}: {}", path.display(), e))?;
+    let endowned = parse_endowments(&json)?;
+
+    get_chain_spec_with(
+        endowned,
+        get_account_id_from_seed::<sr25519::Public>("Alice"),
+        default_authorities(),
+        GenesisOptions::default(),
     )
 }
 
+/// Parse and validate a JSON list of `[account, balance]` endowments.
+///
+/// Fails if an account is endowed more than once, or if the total supply overflows `Balance`.
+fn parse_endowments(json: &str) -> Result<Vec<(AccountId, Balance)>, String> {
+    let endowned: Vec<(AccountId, Balance)> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid endowments: {}", e))?;
+
+    let mut accounts = BTreeSet::new();
+    let mut total_supply: Balance = 0;
+    for (account, balance) in &endowned {
+        if !accounts.insert(account) {
+            return Err(format!("Duplicated endowed account {}", account));
+        }
+        total_supply = total_supply
+            .checked_add(*balance)
+            .ok_or_else(|| "Total supply of endowments overflows".to_string())?;
+    }
+
+    Ok(endowned)
+}
+
 fn session_keys(aura: AuraId) -> shiden_runtime::SessionKeys {
     shiden_runtime::SessionKeys { aura }
 }
 
-/// Helper function to create GenesisConfig.
-fn make_genesis(
-    balances: Vec<(AccountId, Balance)>,
-    root_key: AccountId,
-    parachain_id: ParaId,
-) -> shiden_runtime::GenesisConfig {
-    let authorities = vec![
+/// Alice and Bob as the default collator authorities.
+pub fn default_authorities() -> Vec<(AccountId, AuraId)> {
+    vec![
         (
             get_account_id_from_seed::<sr25519::Public>("Alice"),
             get_from_seed::<AuraId>("Alice"),
@@ -94,14 +292,65 @@ fn make_genesis(
             get_account_id_from_seed::<sr25519::Public>("Bob"),
             get_from_seed::<AuraId>("Bob"),
         ),
-    ];
+    ]
+}
+
+/// Block reward distribution of `options`, or the development defaults.
+fn reward_config(options: &GenesisOptions) -> pallet_block_reward::RewardDistributionConfig {
+    options
+        .reward_config
+        .clone()
+        .unwrap_or_else(|| pallet_block_reward::RewardDistributionConfig {
+            base_treasury_percent: Perbill::from_percent(40),
+            base_staker_percent: Perbill::from_percent(25),
+            dapps_percent: Perbill::from_percent(25),
+            collators_percent: Perbill::from_percent(10),
+            adjustable_percent: Perbill::from_percent(0),
+            ideal_dapps_staking_tvl: Perbill::from_percent(0),
+        })
+}
 
-    // This is supposed the be the simplest bytecode to revert without returning any data.
-    // We will pre-deploy it under all of our precompiles to ensure they can be called from
-    // within contracts.
-    // (PUSH1 0x00 PUSH1 0x00 REVERT)
-    let revert_bytecode = vec![0x60, 0x00, 0x60, 0x00, 0xFD];
+/// Check `options` can be applied to the genesis with the `balances` endowments.
+///
+/// Fails if the reward distribution doesn't sum up to 100%, or if a vesting schedule locks
+/// more than the endowment of its account.
+fn check_options(
+    balances: &[(AccountId, Balance)],
+    options: &GenesisOptions,
+) -> Result<(), String> {
+    let reward_config = reward_config(options);
+    if !reward_config.is_consistent() {
+        return Err(format!(
+            "Reward distribution must sum up to 100%, got {:?}.",
+            reward_config
+        ));
+    }
+    for (account, _, _, liquid) in &options.vesting {
+        let endowment = balances
+            .iter()
+            .find(|(endowed, _)| endowed == account)
+            .map(|(_, balance)| *balance);
+        if endowment.map_or(true, |endowment| *liquid > endowment) {
+            return Err(format!(
+                "Vesting schedule of {} exceeds its endowment {:?}.",
+                account, endowment
+            ));
+        }
+    }
+    Ok(())
+}
 
+/// Helper function to create GenesisConfig.
+///
+/// Collator invulnerables and session keys are derived from `authorities`, `options` must
+/// pass `check_options`.
+fn make_genesis(
+    balances: Vec<(AccountId, Balance)>,
+    root_key: AccountId,
+    authorities: Vec<(AccountId, AuraId)>,
+    parachain_id: ParaId,
+    options: &GenesisOptions,
+) -> shiden_runtime::GenesisConfig {
     shiden_runtime::GenesisConfig {
         system: SystemConfig {
             code: wasm_binary_unwrap().to_vec(),
@@ -112,17 +361,11 @@ fn make_genesis(
         parachain_info: ParachainInfoConfig { parachain_id },
         balances: shiden_runtime::BalancesConfig { balances },
         block_reward: BlockRewardConfig {
-            // Make sure sum is 100
-            reward_config: pallet_block_reward::RewardDistributionConfig {
-                base_treasury_percent: Perbill::from_percent(40),
-                base_staker_percent: Perbill::from_percent(25),
-                dapps_percent: Perbill::from_percent(25),
-                collators_percent: Perbill::from_percent(10),
-                adjustable_percent: Perbill::from_percent(0),
-                ideal_dapps_staking_tvl: Perbill::from_percent(0),
-            },
+            reward_config: reward_config(options),
+        },
+        vesting: shiden_runtime::VestingConfig {
+            vesting: options.vesting.clone(),
         },
-        vesting: shiden_runtime::VestingConfig { vesting: vec![] },
         session: shiden_runtime::SessionConfig {
             keys: authorities
                 .iter()
@@ -133,32 +376,39 @@ fn make_genesis(
             authorities: vec![],
         },
         aura_ext: Default::default(),
-        collator_selection: shiden_runtime::CollatorSelectionConfig {
-            desired_candidates: 32,
-            candidacy_bond: 32_000 * SDN,
-            invulnerables: authorities.iter().map(|x| x.0.clone()).collect::<Vec<_>>(),
-        },
+        collator_selection: collator_selection_config(
+            authorities.iter().map(|x| x.0.clone()).collect::<Vec<_>>(),
+            options.disable_collator_bonds,
+        ),
         evm: EVMConfig {
             // We need _some_ code inserted at the precompile address so that
             // the evm will actually call the address.
             accounts: Precompiles::used_addresses()
-                .filter(|addr| !Precompiles::is_blacklisted(addr))
-                .map(|addr| {
+                .filter(|addr| {
+                    options.include_blacklisted_precompiles || !Precompiles::is_blacklisted(addr)
+                })
+                .map(|addr| (addr, PRECOMPILE_REVERT_BYTECODE.to_vec()))
+                .chain(options.precompile_stubs.clone())
+                .map(|(addr, code)| {
                     (
                         addr,
                         fp_evm::GenesisAccount {
                             nonce: Default::default(),
                             balance: Default::default(),
                             storage: Default::default(),
-                            code: revert_bytecode.clone(),
+                            code,
                         },
                     )
                 })
                 .collect(),
         },
         base_fee: BaseFeeConfig::new(
-            sp_core::U256::from(1_000_000_000),
-            sp_runtime::Permill::zero(),
+            options
+                .base_fee_per_gas
+                .unwrap_or_else(|| sp_core::U256::from(1_000_000_000)),
+            options
+                .base_fee_elasticity
+                .unwrap_or_else(sp_runtime::Permill::zero),
         ),
         ethereum: Default::default(),
         polkadot_xcm: Default::default(),
@@ -168,6 +418,28 @@ fn make_genesis(
     }
 }
 
+/// Collator selection genesis config.
+///
+/// If `disable_bonds` is set, candidacy bond is zero and only the invulnerables are desired.
+fn collator_selection_config(
+    invulnerables: Vec<AccountId>,
+    disable_bonds: bool,
+) -> CollatorSelectionConfig {
+    if disable_bonds {
+        CollatorSelectionConfig {
+            desired_candidates: invulnerables.len() as u32,
+            candidacy_bond: 0,
+            invulnerables,
+        }
+    } else {
+        CollatorSelectionConfig {
+            desired_candidates: 32,
+            candidacy_bond: 32_000 * SDN,
+            invulnerables,
+        }
+    }
+}
+
 type AccountPublic = <Signature as Verify>::Signer;
 
 /// Helper function to generate an account ID from seed
@@ -177,3 +449,334 @@ where
 {
     AccountPublic::from(get_from_seed::<TPublic>(seed)).into_account()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_runtime::BuildStorage;
+
+    fn dev_genesis(options: GenesisOptions) -> shiden_runtime::GenesisConfig {
+        make_genesis(
+            vec![(
+                get_account_id_from_seed::<sr25519::Public>("Alice"),
+                1_000_000_000 * SDN,
+            )],
+            get_account_id_from_seed::<sr25519::Public>("Alice"),
+            default_authorities(),
+            PARA_ID.into(),
+            &options,
+        )
+    }
+
+    #[test]
+    fn local_chain_spec_shares_dev_genesis() {
+        use sc_service::ChainSpec;
+
+        let dev_spec = get_chain_spec(None);
+        let local_spec = get_local_chain_spec();
+        assert_eq!(local_spec.chain_type(), ChainType::Local);
+        assert_eq!(dev_spec.chain_type(), ChainType::Development);
+        assert!(local_spec.boot_nodes().is_empty());
+        assert_eq!(local_spec.protocol_id(), dev_spec.protocol_id());
+        assert_eq!(local_spec.properties(), dev_spec.properties());
+        assert_eq!(
+            Extensions::try_get(&local_spec).map(|e| (&e.relay_chain, e.para_id)),
+            Extensions::try_get(&dev_spec).map(|e| (&e.relay_chain, e.para_id)),
+        );
+        assert_eq!(
+            local_spec.build_storage().unwrap(),
+            dev_spec.build_storage().unwrap()
+        );
+    }
+
+    #[test]
+    fn relay_chain_and_para_id_can_be_configured() {
+        let spec = get_chain_spec_for("rococo", 2000);
+        assert_eq!(
+            Extensions::try_get(&spec).map(|e| (e.relay_chain.as_str(), e.para_id)),
+            Some(("rococo", 2000)),
+        );
+        sp_io::TestExternalities::new(spec.build_storage().unwrap()).execute_with(|| {
+            assert_eq!(
+                shiden_runtime::ParachainInfo::parachain_id(),
+                ParaId::from(2000)
+            );
+        });
+
+        let spec = get_chain_spec(None);
+        assert_eq!(
+            Extensions::try_get(&spec).map(|e| (e.relay_chain.as_str(), e.para_id)),
+            Some((RELAY_CHAIN, PARA_ID)),
+        );
+    }
+
+    #[test]
+    fn telemetry_endpoints_can_be_configured() {
+        use sc_service::ChainSpec;
+
+        assert!(get_chain_spec(None).telemetry_endpoints().is_none());
+        assert!(get_chain_spec(Some(polkadot_telemetry_endpoints()))
+            .telemetry_endpoints()
+            .is_some());
+    }
+
+    #[test]
+    fn authorities_can_be_configured() {
+        let authorities = ["Alice", "Bob", "Charlie", "Dave"]
+            .iter()
+            .map(|seed| {
+                (
+                    get_account_id_from_seed::<sr25519::Public>(seed),
+                    get_from_seed::<AuraId>(seed),
+                )
+            })
+            .collect::<Vec<_>>();
+        let genesis = make_genesis(
+            vec![],
+            get_account_id_from_seed::<sr25519::Public>("Alice"),
+            authorities.clone(),
+            PARA_ID.into(),
+            &GenesisOptions::default(),
+        );
+
+        assert_eq!(
+            genesis.collator_selection.invulnerables,
+            authorities.iter().map(|x| x.0.clone()).collect::<Vec<_>>()
+        );
+        assert_eq!(genesis.session.keys.len(), 4);
+        for ((account, _, keys), (authority, aura)) in genesis.session.keys.iter().zip(&authorities)
+        {
+            assert_eq!(account, authority);
+            assert_eq!(keys.aura, *aura);
+        }
+    }
+
+    #[test]
+    fn custom_endowment_is_applied() {
+        let charlie = get_account_id_from_seed::<sr25519::Public>("Charlie");
+        let storage = get_chain_spec_with(
+            vec![(charlie.clone(), 42 * SDN)],
+            charlie.clone(),
+            default_authorities(),
+            GenesisOptions::default(),
+        )
+        .unwrap()
+        .build_storage()
+        .unwrap();
+
+        sp_io::TestExternalities::new(storage).execute_with(|| {
+            assert_eq!(shiden_runtime::Balances::free_balance(&charlie), 42 * SDN);
+            assert_eq!(shiden_runtime::Sudo::key(), Some(charlie));
+        });
+    }
+
+    #[test]
+    fn endowments_can_be_loaded_from_json() {
+        let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+        let bob = get_account_id_from_seed::<sr25519::Public>("Bob");
+        // Unique per process, so concurrent test runs don't share the file.
+        let path = std::env::temp_dir().join(format!(
+            "shiden-endowments-test-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            format!(r#"[["{}", 42], ["{}", 1000000000000000000]]"#, alice, bob),
+        )
+        .unwrap();
+
+        let storage = get_chain_spec_from_json(&path)
+            .unwrap()
+            .build_storage()
+            .unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        sp_io::TestExternalities::new(storage).execute_with(|| {
+            assert_eq!(shiden_runtime::Balances::free_balance(&alice), 42);
+            assert_eq!(shiden_runtime::Balances::free_balance(&bob), SDN);
+        });
+    }
+
+    #[test]
+    fn invalid_endowments_are_rejected() {
+        let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+        let bob = get_account_id_from_seed::<sr25519::Public>("Bob");
+
+        assert!(parse_endowments("not json").is_err());
+        assert!(parse_endowments(r#"[["invalid account", 1]]"#).is_err());
+        assert_eq!(
+            parse_endowments(&format!(r#"[["{}", 1], ["{}", 2]]"#, alice, alice)),
+            Err(format!("Duplicated endowed account {}", alice)),
+        );
+        assert_eq!(
+            parse_endowments(&format!(
+                r#"[["{}", {}], ["{}", 1]]"#,
+                alice,
+                Balance::MAX,
+                bob
+            )),
+            Err("Total supply of endowments overflows".to_string()),
+        );
+        assert_eq!(
+            parse_endowments(&format!(r#"[["{}", 1], ["{}", 2]]"#, alice, bob)),
+            Ok(vec![(alice, 1), (bob, 2)]),
+        );
+    }
+
+    #[test]
+    fn reward_config_can_be_configured() {
+        let reward_config = pallet_block_reward::RewardDistributionConfig {
+            base_treasury_percent: Perbill::from_percent(10),
+            base_staker_percent: Perbill::from_percent(20),
+            dapps_percent: Perbill::from_percent(30),
+            collators_percent: Perbill::from_percent(40),
+            adjustable_percent: Perbill::from_percent(0),
+            ideal_dapps_staking_tvl: Perbill::from_percent(0),
+        };
+        let genesis = dev_genesis(GenesisOptions {
+            reward_config: Some(reward_config.clone()),
+            ..Default::default()
+        });
+        assert_eq!(genesis.block_reward.reward_config, reward_config);
+    }
+
+    #[test]
+    fn default_reward_config_is_consistent() {
+        let genesis = dev_genesis(GenesisOptions::default());
+        assert!(genesis.block_reward.reward_config.is_consistent());
+    }
+
+    #[test]
+    fn inconsistent_reward_config_is_rejected() {
+        let result = get_chain_spec_with_options(GenesisOptions {
+            reward_config: Some(pallet_block_reward::RewardDistributionConfig {
+                base_treasury_percent: Perbill::from_percent(50),
+                base_staker_percent: Perbill::from_percent(50),
+                dapps_percent: Perbill::from_percent(50),
+                collators_percent: Perbill::from_percent(0),
+                adjustable_percent: Perbill::from_percent(0),
+                ideal_dapps_staking_tvl: Perbill::from_percent(0),
+            }),
+            ..Default::default()
+        });
+        assert!(result
+            .unwrap_err()
+            .starts_with("Reward distribution must sum up to 100%"));
+    }
+
+    #[test]
+    fn vesting_can_be_configured() {
+        let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+        let schedule = (alice.clone(), 10, 100, 1_000 * SDN);
+        let genesis = dev_genesis(GenesisOptions {
+            vesting: vec![schedule.clone()],
+            ..Default::default()
+        });
+        assert_eq!(genesis.vesting.vesting, vec![schedule]);
+
+        sp_io::TestExternalities::new(genesis.build_storage().unwrap()).execute_with(|| {
+            assert_eq!(
+                shiden_runtime::Vesting::vesting_balance(&alice),
+                Some(1_000_000_000 * SDN - 1_000 * SDN)
+            );
+        });
+    }
+
+    #[test]
+    fn vesting_exceeding_endowment_is_rejected() {
+        let alice = get_account_id_from_seed::<sr25519::Public>("Alice");
+        let charlie = get_account_id_from_seed::<sr25519::Public>("Charlie");
+        let options = |account: &AccountId, liquid: Balance| GenesisOptions {
+            vesting: vec![(account.clone(), 10, 100, liquid)],
+            ..Default::default()
+        };
+
+        assert!(get_chain_spec_with_options(options(&alice, 1_000_000_000 * SDN)).is_ok());
+        assert!(
+            get_chain_spec_with_options(options(&alice, 1_000_000_000 * SDN + 1))
+                .unwrap_err()
+                .contains("exceeds its endowment")
+        );
+        // Vesting accounts must be endowed.
+        assert!(get_chain_spec_with_options(options(&charlie, 1))
+            .unwrap_err()
+            .contains("exceeds its endowment"));
+    }
+
+    #[test]
+    fn precompile_stubs_are_predeployed() {
+        let stubbed = |genesis: &shiden_runtime::GenesisConfig| {
+            genesis.evm.accounts.keys().copied().collect::<Vec<_>>()
+        };
+
+        let genesis = dev_genesis(GenesisOptions::default());
+        let expected = Precompiles::used_addresses()
+            .filter(|addr| !Precompiles::is_blacklisted(addr))
+            .collect::<BTreeSet<_>>();
+        assert_eq!(stubbed(&genesis), expected.into_iter().collect::<Vec<_>>());
+        assert!(genesis
+            .evm
+            .accounts
+            .values()
+            .all(|account| account.code == PRECOMPILE_REVERT_BYTECODE));
+
+        let genesis = dev_genesis(GenesisOptions {
+            include_blacklisted_precompiles: true,
+            ..Default::default()
+        });
+        let expected = Precompiles::used_addresses().collect::<BTreeSet<_>>();
+        assert_eq!(stubbed(&genesis), expected.into_iter().collect::<Vec<_>>());
+
+        let precompile = Precompiles::used_addresses().next().unwrap();
+        let other = H160::repeat_byte(0x11);
+        let genesis = dev_genesis(GenesisOptions {
+            precompile_stubs: BTreeMap::from([(precompile, vec![0x00]), (other, vec![0x00])]),
+            ..Default::default()
+        });
+        let mut expected = Precompiles::used_addresses()
+            .filter(|addr| !Precompiles::is_blacklisted(addr))
+            .collect::<BTreeSet<_>>();
+        expected.insert(other);
+        assert_eq!(stubbed(&genesis), expected.into_iter().collect::<Vec<_>>());
+        assert_eq!(genesis.evm.accounts[&precompile].code, vec![0x00]);
+        assert_eq!(genesis.evm.accounts[&other].code, vec![0x00]);
+    }
+
+    #[test]
+    fn base_fee_can_be_configured() {
+        let genesis = dev_genesis(GenesisOptions::default());
+        assert_eq!(
+            genesis.base_fee.base_fee_per_gas,
+            sp_core::U256::from(1_000_000_000)
+        );
+        assert_eq!(genesis.base_fee.elasticity, sp_runtime::Permill::zero());
+
+        let genesis = dev_genesis(GenesisOptions {
+            base_fee_per_gas: Some(sp_core::U256::from(42)),
+            base_fee_elasticity: Some(sp_runtime::Permill::from_parts(125_000)),
+            ..Default::default()
+        });
+        assert_eq!(genesis.base_fee.base_fee_per_gas, sp_core::U256::from(42));
+        assert_eq!(
+            genesis.base_fee.elasticity,
+            sp_runtime::Permill::from_parts(125_000)
+        );
+    }
+
+    #[test]
+    fn collator_bonds_can_be_disabled() {
+        let genesis = dev_genesis(GenesisOptions {
+            disable_collator_bonds: true,
+            ..Default::default()
+        });
+        assert_eq!(genesis.collator_selection.candidacy_bond, 0);
+        assert_eq!(
+            genesis.collator_selection.desired_candidates,
+            genesis.collator_selection.invulnerables.len() as u32
+        );
+
+        let genesis = dev_genesis(GenesisOptions::default());
+        assert_eq!(genesis.collator_selection.candidacy_bond, 32_000 * SDN);
+        assert_eq!(genesis.collator_selection.desired_candidates, 32);
+    }
+}
@@ -19,12 +19,15 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use astar_primitives::{
-    xvm::{FailureError, FailureReason, FailureRevert},
+    xvm::{CallFailure, ContextError, FailureError, FailureReason, FailureRevert, VmId},
     Balance,
 };
 use parity_scale_codec::{Decode, Encode};
 use sp_std::vec::Vec;
 
+#[cfg(test)]
+mod tests;
+
 #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
 #[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, Debug)]
 pub enum XvmExecutionResult {
@@ -42,12 +45,47 @@ impl From<FailureReason> for XvmExecutionResult {
             FailureReason::Revert(FailureRevert::InvalidTarget) => 1,
             FailureReason::Revert(FailureRevert::InputTooLarge) => 2,
             FailureReason::Revert(FailureRevert::VmRevert(_)) => 3,
+            FailureReason::Revert(FailureRevert::AccessListTooLarge) => 4,
+            FailureReason::Revert(FailureRevert::OutputDecodeFailed) => 5,
+            FailureReason::Revert(FailureRevert::OutputTooLarge) => 6,
+            FailureReason::Revert(FailureRevert::TargetVmMismatch) => 7,
+            FailureReason::Revert(FailureRevert::GasLimitTooHigh) => 8,
+            FailureReason::Revert(FailureRevert::EmptyTarget) => 9,
+            FailureReason::Revert(FailureRevert::TargetNotAllowed) => 10,
 
             // Error failure: 128 - 255
             FailureReason::Error(FailureError::InvalidVmId) => 128,
             FailureReason::Error(FailureError::SameVmCallDenied) => 129,
             FailureReason::Error(FailureError::ReentranceDenied) => 130,
             FailureReason::Error(FailureError::VmError(_)) => 131,
+            FailureReason::Error(FailureError::NonceMismatch) => 132,
+            FailureReason::Error(FailureError::OutOfStorageDeposit) => 133,
+            FailureReason::Error(FailureError::InsufficientBalance) => 134,
+            FailureReason::Error(FailureError::WouldReapAccount) => 135,
+            FailureReason::Error(FailureError::GasLimitRequired) => 136,
+            FailureReason::Error(FailureError::VmNotSupported(_)) => 137,
+            FailureReason::Error(FailureError::ValueTooLarge) => 138,
+            FailureReason::Error(FailureError::OutOfGas) => 139,
+            FailureReason::Error(FailureError::UntrustedCaller) => 140,
+            FailureReason::Error(FailureError::Paused) => 141,
+            FailureReason::Error(FailureError::ContractTrapped) => 142,
+            FailureReason::Error(FailureError::ContractNotFound) => 143,
+            FailureReason::Error(FailureError::CallLimitReached) => 144,
+            FailureReason::Error(FailureError::SelfCallDenied) => 145,
+            FailureReason::Error(FailureError::BalanceConversionOverflow) => 146,
+            FailureReason::Error(FailureError::TargetRateLimited) => 147,
+            FailureReason::Error(FailureError::BelowExistentialDeposit) => 148,
+        };
+        Self::Err(error_code)
+    }
+}
+
+impl From<ContextError> for XvmExecutionResult {
+    /// The context of the call is invalid, the call isn't executed. Its codes follow the error
+    /// failures.
+    fn from(input: ContextError) -> Self {
+        let error_code = match input {
+            ContextError::ZeroWeightLimit => 149,
         };
         Self::Err(error_code)
     }
@@ -62,6 +100,97 @@ impl From<XvmExecutionResult> for u32 {
     }
 }
 
+/// Version of the `XvmFailure` wire format.
+///
+/// Adding a failure code is backward compatible and keeps the version. Any other change is
+/// breaking and bumps it: changing the fields or their encoding, or changing the meaning of
+/// an existing code, including reusing a removed one.
+pub const XVM_FAILURE_VERSION: u8 = 1;
+
+/// Versioned wire format of XVM call failures, for a stable layout in WASM contracts.
+#[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Debug)]
+pub struct XvmFailure {
+    /// Wire format version, `XVM_FAILURE_VERSION` if converted by this crate.
+    pub version: u8,
+    /// Failure code, as in `XvmExecutionResult::Err`.
+    pub code: u32,
+    /// The revert data of `VmRevert`, the message of `VmError`, or the encoded `VmId` of
+    /// `VmNotSupported`. Empty for other failures.
+    pub data: Vec<u8>,
+}
+
+impl From<FailureReason> for XvmFailure {
+    fn from(reason: FailureReason) -> Self {
+        let data = match &reason {
+            FailureReason::Revert(FailureRevert::VmRevert(data)) => data.clone(),
+            FailureReason::Error(FailureError::VmError(msg)) => msg.clone(),
+            FailureReason::Error(FailureError::VmNotSupported(vm_id)) => vm_id.encode(),
+            _ => Vec::new(),
+        };
+        Self {
+            version: XVM_FAILURE_VERSION,
+            code: XvmExecutionResult::from(reason).into(),
+            data,
+        }
+    }
+}
+
+impl From<CallFailure> for XvmFailure {
+    fn from(failure: CallFailure) -> Self {
+        failure.reason.into()
+    }
+}
+
+impl TryFrom<XvmFailure> for FailureReason {
+    type Error = ();
+
+    /// Fails if the version or the code is unknown, or the data doesn't match the code.
+    fn try_from(failure: XvmFailure) -> Result<Self, Self::Error> {
+        if failure.version != XVM_FAILURE_VERSION {
+            return Err(());
+        }
+        let reason = match failure.code {
+            1 => FailureReason::Revert(FailureRevert::InvalidTarget),
+            2 => FailureReason::Revert(FailureRevert::InputTooLarge),
+            3 => FailureReason::Revert(FailureRevert::VmRevert(failure.data)),
+            4 => FailureReason::Revert(FailureRevert::AccessListTooLarge),
+            5 => FailureReason::Revert(FailureRevert::OutputDecodeFailed),
+            6 => FailureReason::Revert(FailureRevert::OutputTooLarge),
+            7 => FailureReason::Revert(FailureRevert::TargetVmMismatch),
+            8 => FailureReason::Revert(FailureRevert::GasLimitTooHigh),
+            9 => FailureReason::Revert(FailureRevert::EmptyTarget),
+            10 => FailureReason::Revert(FailureRevert::TargetNotAllowed),
+
+            128 => FailureReason::Error(FailureError::InvalidVmId),
+            129 => FailureReason::Error(FailureError::SameVmCallDenied),
+            130 => FailureReason::Error(FailureError::ReentranceDenied),
+            131 => FailureReason::Error(FailureError::VmError(failure.data)),
+            132 => FailureReason::Error(FailureError::NonceMismatch),
+            133 => FailureReason::Error(FailureError::OutOfStorageDeposit),
+            134 => FailureReason::Error(FailureError::InsufficientBalance),
+            135 => FailureReason::Error(FailureError::WouldReapAccount),
+            136 => FailureReason::Error(FailureError::GasLimitRequired),
+            137 => FailureReason::Error(FailureError::VmNotSupported(
+                VmId::decode(&mut &failure.data[..]).map_err(|_| ())?,
+            )),
+            138 => FailureReason::Error(FailureError::ValueTooLarge),
+            139 => FailureReason::Error(FailureError::OutOfGas),
+            140 => FailureReason::Error(FailureError::UntrustedCaller),
+            141 => FailureReason::Error(FailureError::Paused),
+            142 => FailureReason::Error(FailureError::ContractTrapped),
+            143 => FailureReason::Error(FailureError::ContractNotFound),
+            144 => FailureReason::Error(FailureError::CallLimitReached),
+            145 => FailureReason::Error(FailureError::SelfCallDenied),
+            146 => FailureReason::Error(FailureError::BalanceConversionOverflow),
+            147 => FailureReason::Error(FailureError::TargetRateLimited),
+            148 => FailureReason::Error(FailureError::BelowExistentialDeposit),
+            _ => return Err(()),
+        };
+        Ok(reason)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Encode, Decode, Debug)]
 pub struct XvmCallArgs {
     /// virtual machine identifier
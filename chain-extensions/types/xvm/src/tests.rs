@@ -0,0 +1,123 @@
+// This file is part of Astar.
+
+// Copyright (C) 2019-2023 Stake Technologies Pte.Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Astar is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Astar is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Astar. If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+#[test]
+fn xvm_failure_round_trips() {
+    let reasons = vec![
+        FailureReason::Revert(FailureRevert::InvalidTarget),
+        FailureReason::Revert(FailureRevert::InputTooLarge),
+        FailureReason::Revert(FailureRevert::VmRevert(vec![1, 2, 3])),
+        FailureReason::Revert(FailureRevert::AccessListTooLarge),
+        FailureReason::Revert(FailureRevert::OutputDecodeFailed),
+        FailureReason::Revert(FailureRevert::OutputTooLarge),
+        FailureReason::Revert(FailureRevert::TargetVmMismatch),
+        FailureReason::Revert(FailureRevert::GasLimitTooHigh),
+        FailureReason::Revert(FailureRevert::EmptyTarget),
+        FailureReason::Revert(FailureRevert::TargetNotAllowed),
+        FailureReason::Error(FailureError::InvalidVmId),
+        FailureReason::Error(FailureError::SameVmCallDenied),
+        FailureReason::Error(FailureError::ReentranceDenied),
+        FailureReason::Error(FailureError::VmError(b"EVM call error".to_vec())),
+        FailureReason::Error(FailureError::NonceMismatch),
+        FailureReason::Error(FailureError::OutOfStorageDeposit),
+        FailureReason::Error(FailureError::InsufficientBalance),
+        FailureReason::Error(FailureError::WouldReapAccount),
+        FailureReason::Error(FailureError::GasLimitRequired),
+        FailureReason::Error(FailureError::VmNotSupported(VmId::Wasm)),
+        FailureReason::Error(FailureError::ValueTooLarge),
+        FailureReason::Error(FailureError::OutOfGas),
+        FailureReason::Error(FailureError::UntrustedCaller),
+        FailureReason::Error(FailureError::Paused),
+        FailureReason::Error(FailureError::ContractTrapped),
+        FailureReason::Error(FailureError::ContractNotFound),
+        FailureReason::Error(FailureError::CallLimitReached),
+        FailureReason::Error(FailureError::SelfCallDenied),
+        FailureReason::Error(FailureError::BalanceConversionOverflow),
+        FailureReason::Error(FailureError::TargetRateLimited),
+        FailureReason::Error(FailureError::BelowExistentialDeposit),
+    ];
+
+    for reason in reasons {
+        let failure = XvmFailure::from(reason.clone());
+        assert_eq!(failure.version, XVM_FAILURE_VERSION);
+        assert_eq!(
+            XvmExecutionResult::Err(failure.code),
+            XvmExecutionResult::from(reason.clone())
+        );
+
+        let decoded = XvmFailure::decode(&mut &failure.encode()[..]).expect("decodable");
+        assert_eq!(decoded, failure);
+        assert_eq!(FailureReason::try_from(decoded), Ok(reason));
+    }
+}
+
+#[test]
+fn xvm_failure_layout_is_stable() {
+    let failure = XvmFailure::from(FailureReason::Revert(FailureRevert::VmRevert(vec![7])));
+    // Version, little endian code, compact length prefixed data.
+    assert_eq!(failure.encode(), vec![1, 3, 0, 0, 0, 4, 7]);
+}
+
+#[test]
+fn xvm_failure_conversion_fails_if_unknown() {
+    let failure = XvmFailure {
+        version: XVM_FAILURE_VERSION,
+        code: 1,
+        data: vec![],
+    };
+    assert_eq!(
+        FailureReason::try_from(failure.clone()),
+        Ok(FailureReason::Revert(FailureRevert::InvalidTarget))
+    );
+
+    // Unknown version.
+    assert_eq!(
+        FailureReason::try_from(XvmFailure {
+            version: XVM_FAILURE_VERSION + 1,
+            ..failure.clone()
+        }),
+        Err(())
+    );
+    // Unknown code.
+    assert_eq!(
+        FailureReason::try_from(XvmFailure {
+            code: 127,
+            ..failure.clone()
+        }),
+        Err(())
+    );
+    // Invalid VM id of `VmNotSupported`.
+    assert_eq!(
+        FailureReason::try_from(XvmFailure {
+            code: 137,
+            data: vec![0x42],
+            ..failure
+        }),
+        Err(())
+    );
+}
+
+#[test]
+fn context_error_code_follows_failure_codes() {
+    assert_eq!(
+        XvmExecutionResult::from(ContextError::ZeroWeightLimit),
+        XvmExecutionResult::Err(149)
+    );
+}
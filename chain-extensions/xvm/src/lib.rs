@@ -23,7 +23,7 @@ use alloc::format;
 
 use astar_primitives::{
     evm::UnifiedAddressMapper,
-    xvm::{Context, VmId, XvmCall},
+    xvm::{ContextBuilder, VmId, WeightBreakdown, XvmCall},
 };
 use frame_support::{dispatch::Encode, traits::Get, weights::Weight};
 use frame_system::RawOrigin;
@@ -33,7 +33,7 @@ use pallet_contracts::chain_extension::{
 use pallet_unified_accounts::WeightInfo;
 use sp_runtime::DispatchError;
 use sp_std::marker::PhantomData;
-use xvm_chain_extension_types::{XvmCallArgs, XvmExecutionResult};
+use xvm_chain_extension_types::{XvmCallArgs, XvmExecutionResult, XvmFailure};
 
 enum XvmFuncId {
     Call,
@@ -118,9 +118,15 @@ where
                     }
                 }
 
-                let xvm_context = Context {
-                    source_vm_id: VmId::Wasm,
-                    weight_limit,
+                let xvm_context = match ContextBuilder::new(VmId::Wasm)
+                    .weight_limit(weight_limit)
+                    .build()
+                {
+                    Ok(context) => context,
+                    Err(err) => {
+                        env.adjust_weight(charged_weight, actual_weight);
+                        return Ok(RetVal::Converging(XvmExecutionResult::from(err).into()));
+                    }
                 };
                 let vm_id = {
                     match TryInto::<VmId>::try_into(vm_id) {
@@ -135,11 +141,14 @@ where
                 };
                 let call_result = XC::call(xvm_context, vm_id, source, to, input, value, None);
 
-                let used_weight = match call_result {
-                    Ok(ref info) => info.used_weight,
-                    Err(ref err) => err.used_weight,
-                };
-                actual_weight.saturating_accrue(used_weight);
+                // Only the used weight is charged, the unused weight of the limit is refunded.
+                let breakdown =
+                    WeightBreakdown::new(weight_limit, XC::call_overheads(vm_id), &call_result);
+                log::trace!(
+                    target: "xvm-extension::xvm_call",
+                    "weight: {:?}", breakdown
+                );
+                actual_weight.saturating_accrue(breakdown.total_used);
                 env.adjust_weight(charged_weight, actual_weight);
 
                 match call_result {
@@ -161,10 +170,11 @@ where
                         );
 
                         // `Diverging` is used instead of `Err` to make sure the control
-                        // doesn't return to the caller.
+                        // doesn't return to the caller. The revert data is the SCALE encoded
+                        // `XvmFailure`, to be decoded by the caller.
                         Ok(RetVal::Diverging {
                             flags: ReturnFlags::REVERT,
-                            data: format!("{:?}", err).into(),
+                            data: XvmFailure::from(err).encode(),
                         })
                     }
                 }
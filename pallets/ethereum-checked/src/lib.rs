@@ -299,4 +299,14 @@ impl<T: Config> CheckedEthereumTransact for Pallet<T> {
     ) -> Result<(PostDispatchInfo, CallInfo), DispatchErrorWithPostInfo> {
         Self::do_transact(source, checked_tx, CheckedEthereumTxKind::Xvm, false)
     }
+
+    fn account_nonce(source: H160) -> U256 {
+        let (account, _) = pallet_evm::Pallet::<T>::account_basic(&source);
+        account.nonce
+    }
+
+    fn account_balance(address: H160) -> U256 {
+        let (account, _) = pallet_evm::Pallet::<T>::account_basic(&address);
+        account.balance
+    }
 }
@@ -19,16 +19,13 @@
 use super::*;
 
 use frame_benchmarking::v2::*;
-use frame_support::weights::Weight;
+use frame_support::{traits::EnsureOrigin, weights::Weight};
+use frame_system::RawOrigin;
 use parity_scale_codec::Encode;
 use sp_core::H160;
 use sp_runtime::MultiAddress;
 
-use astar_primitives::Balance;
-
-#[benchmarks(
-    where <T as pallet_contracts::Config>::Currency: Currency<T::AccountId, Balance = Balance>,
-)]
+#[benchmarks]
 mod benchmarks {
     use super::*;
 
@@ -37,12 +34,21 @@ mod benchmarks {
         let context = Context {
             source_vm_id: VmId::Wasm,
             weight_limit: Weight::from_parts(1_000_000, 1_000_000),
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output: false,
+            record_call_graph: false,
         };
         let vm_id = VmId::Evm;
-        let source = whitelisted_caller();
+        let source: T::AccountId = whitelisted_caller();
         let target = H160::repeat_byte(1).encode();
         let input = vec![1, 2, 3];
         let value = 1_000_000u128;
+        // The value is debited from the mapped EVM account of the source.
+        T::BenchmarkHelper::set_evm_balance(
+            Pallet::<T>::mapped_evm_address(source.clone()),
+            value + <T as Config>::Currency::minimum_balance(),
+        );
 
         #[block]
         {
@@ -56,12 +62,21 @@ mod benchmarks {
         let context = Context {
             source_vm_id: VmId::Evm,
             weight_limit: Weight::from_parts(1_000_000, 1_000_000),
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output: false,
+            record_call_graph: false,
         };
         let vm_id = VmId::Wasm;
         let source = whitelisted_caller();
-        let target = MultiAddress::<T::AccountId, ()>::Id(whitelisted_caller()).encode();
+        // A target other than the source, for the worst case of the balance reads.
+        let target = MultiAddress::<T::AccountId, ()>::Id(account("target", 0, 0)).encode();
         let input = vec![1, 2, 3];
         let value = 1_000_000u128;
+        <T as Config>::Currency::make_free_balance_be(
+            &source,
+            value + <T as Config>::Currency::minimum_balance(),
+        );
 
         #[block]
         {
@@ -70,6 +85,106 @@ mod benchmarks {
         }
     }
 
+    #[benchmark]
+    fn call_denied_overheads() {
+        let context = Context {
+            source_vm_id: VmId::Evm,
+            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output: false,
+            record_call_graph: false,
+        };
+        let vm_id = VmId::Evm;
+        let source = whitelisted_caller();
+        let target = H160::repeat_byte(1).encode();
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+
+        #[block]
+        {
+            Pallet::<T>::call_without_execution(context, vm_id, source, target, input, value, None)
+                .unwrap_err();
+        }
+    }
+
+    #[benchmark]
+    fn batch_call_overheads() {
+        let context = Context {
+            source_vm_id: VmId::Wasm,
+            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output: false,
+            record_call_graph: false,
+        };
+        let source = whitelisted_caller();
+
+        #[block]
+        {
+            Pallet::<T>::call_batch(context, source, vec![]);
+        }
+    }
+
+    #[benchmark]
+    fn set_paused() -> Result<(), BenchmarkError> {
+        let origin =
+            T::PauseOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+
+        #[extrinsic_call]
+        _(origin as T::RuntimeOrigin, true);
+
+        assert!(XvmPaused::<T>::get());
+        Ok(())
+    }
+
+    #[benchmark]
+    fn stage_input(n: Linear<0, { T::MaxInputSize::get() }>) {
+        let caller: T::AccountId = whitelisted_caller();
+        <T as Config>::Currency::make_free_balance_be(
+            &caller,
+            T::StagedInputDepositPerByte::get() * T::MaxInputSize::get() as u128
+                + <T as Config>::Currency::minimum_balance(),
+        );
+        // Append to an already staged input, filling up to the max input size.
+        let staged = vec![1; (T::MaxInputSize::get() - n) as usize];
+        Pallet::<T>::stage_input(RawOrigin::Signed(caller.clone()).into(), staged).unwrap();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), vec![1; n as usize]);
+
+        let (input, _, _) = StagedInputs::<T>::get(&caller).unwrap();
+        assert_eq!(input.len() as u32, T::MaxInputSize::get());
+    }
+
+    #[benchmark]
+    fn commit_and_call() {
+        let caller: T::AccountId = whitelisted_caller();
+        <T as Config>::Currency::make_free_balance_be(
+            &caller,
+            T::StagedInputDepositPerByte::get() * T::MaxInputSize::get() as u128
+                + <T as Config>::Currency::minimum_balance(),
+        );
+        let input = vec![1; T::MaxInputSize::get() as usize];
+        Pallet::<T>::stage_input(RawOrigin::Signed(caller.clone()).into(), input).unwrap();
+        // The call is denied before entering the VM, the XVM call is weighted separately by its
+        // overheads and weight limit.
+        XvmPaused::<T>::put(true);
+        let target = MultiAddress::<T::AccountId, ()>::Id(account("target", 0, 0)).encode();
+
+        #[block]
+        {
+            Pallet::<T>::commit_and_call(
+                RawOrigin::Signed(caller.clone()).into(),
+                target,
+                0,
+                Weight::from_parts(1_000_000, 1_000_000),
+                None,
+            )
+            .unwrap_err();
+        }
+    }
+
     impl_benchmark_test_suite!(
         Pallet,
         crate::benchmarking::tests::new_test_ext(),
@@ -0,0 +1,140 @@
+// This file is part of Astar.
+
+// Copyright (C) 2019-2023 Stake Technologies Pte.Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Astar is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Astar is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Astar. If not, see <http://www.gnu.org/licenses/>.
+
+//! EVM tracing for XVM call simulation.
+//!
+//! Tracks the gas used by the top-level EVM frame only, excluding the gas used by its
+//! internal calls, and the code addresses called during execution. Requires the `tracing`
+//! feature of `evm` and `evm-gasometer`.
+
+use evm::tracing::{Event as EvmEvent, EventListener as EvmListener};
+use evm_gasometer::tracing::{Event as GasometerEvent, EventListener as GasometerListener};
+use sp_core::{H160, U256};
+use sp_std::{cell::RefCell, collections::btree_set::BTreeSet, rc::Rc};
+
+/// Traced info of an EVM execution.
+pub struct EvmTrace {
+    /// Gas used by the top-level frame, excluding its internal calls.
+    pub top_level_gas_used: U256,
+    /// Code addresses of all the calls, including the top-level one.
+    pub called_addresses: BTreeSet<H160>,
+}
+
+/// Listener tracking the used gas of the top-level frame and its internal calls.
+#[derive(Default)]
+struct TracingListener {
+    /// Current call depth, `1` for the top-level frame.
+    depth: usize,
+    /// Last used gas reported by the top-level frame.
+    top_level_used_gas: u64,
+    /// Last used gas reported by the internal call being executed.
+    internal_call_used_gas: u64,
+    /// Total used gas of the finished internal calls.
+    internal_used_gas: u64,
+    /// Code addresses of all the calls.
+    called_addresses: BTreeSet<H160>,
+}
+
+impl TracingListener {
+    fn enter(&mut self) {
+        self.depth = self.depth.saturating_add(1);
+        if self.depth == 2 {
+            self.internal_call_used_gas = 0;
+        }
+    }
+
+    fn exit(&mut self) {
+        // Used gas of internal calls is also recorded by the top-level frame
+        // on exit, count it once the internal call finishes.
+        if self.depth == 2 {
+            self.internal_used_gas = self
+                .internal_used_gas
+                .saturating_add(self.internal_call_used_gas);
+        }
+        self.depth = self.depth.saturating_sub(1);
+    }
+}
+
+impl GasometerListener for TracingListener {
+    fn event(&mut self, event: GasometerEvent) {
+        let snapshot = match event {
+            GasometerEvent::RecordCost { snapshot, .. }
+            | GasometerEvent::RecordRefund { snapshot, .. }
+            | GasometerEvent::RecordStipend { snapshot, .. }
+            | GasometerEvent::RecordDynamicCost { snapshot, .. }
+            | GasometerEvent::RecordTransaction { snapshot, .. } => snapshot,
+        };
+        if let Some(snapshot) = snapshot {
+            match self.depth {
+                1 => self.top_level_used_gas = snapshot.used_gas,
+                2 => self.internal_call_used_gas = snapshot.used_gas,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl EvmListener for TracingListener {
+    fn event(&mut self, event: EvmEvent) {
+        match event {
+            EvmEvent::Call { code_address, .. } => {
+                self.called_addresses.insert(code_address);
+                self.enter();
+            }
+            EvmEvent::Create { .. } => self.enter(),
+            EvmEvent::Exit { .. } => self.exit(),
+            _ => {}
+        }
+    }
+}
+
+struct ListenerProxy(Rc<RefCell<TracingListener>>);
+
+impl GasometerListener for ListenerProxy {
+    fn event(&mut self, event: GasometerEvent) {
+        GasometerListener::event(&mut *self.0.borrow_mut(), event);
+    }
+}
+
+impl EvmListener for ListenerProxy {
+    fn event(&mut self, event: EvmEvent) {
+        EvmListener::event(&mut *self.0.borrow_mut(), event);
+    }
+}
+
+/// Execute `f` with EVM tracing, and return its result with the traced info.
+pub fn trace<R>(f: impl FnOnce() -> R) -> (R, EvmTrace) {
+    let listener = Rc::new(RefCell::new(TracingListener::default()));
+    let mut evm_listener = ListenerProxy(Rc::clone(&listener));
+    let mut gasometer_listener = ListenerProxy(Rc::clone(&listener));
+
+    let result = evm::tracing::using(&mut evm_listener, || {
+        evm_gasometer::tracing::using(&mut gasometer_listener, f)
+    });
+
+    let listener = listener.take();
+    let trace = EvmTrace {
+        top_level_gas_used: U256::from(
+            listener
+                .top_level_used_gas
+                .saturating_sub(listener.internal_used_gas),
+        ),
+        called_addresses: listener.called_addresses,
+    };
+    (result, trace)
+}
@@ -40,70 +40,1494 @@
 extern crate alloc;
 use alloc::format;
 
-use fp_evm::ExitReason;
-use frame_support::{ensure, traits::Currency, weights::Weight};
+#[cfg(feature = "evm-vm")]
+use fp_evm::{ExitError, ExitReason};
+use frame_support::{
+    dispatch::{DispatchErrorWithPostInfo, PostDispatchInfo},
+    ensure,
+    storage::with_transaction,
+    traits::{Contains, Currency, Get, IsType, ReservableCurrency},
+    weights::Weight,
+    BoundedVec, PalletId,
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+#[cfg(feature = "wasm-vm")]
 use pallet_contracts::{CollectEvents, DebugInfo, Determinism};
+#[cfg(feature = "wasm-vm")]
 use pallet_contracts_primitives::ReturnFlags;
+#[cfg(feature = "evm-vm")]
 use pallet_evm::GasWeightMapping;
-use parity_scale_codec::Decode;
-use sp_core::{H160, U256};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "evm-vm")]
+use sp_core::U256;
+use sp_core::{H160, H256};
+#[cfg(any(feature = "evm-vm", feature = "wasm-vm"))]
+use sp_runtime::traits::UniqueSaturatedInto;
+use sp_runtime::{
+    traits::{AccountIdConversion, Convert},
+    DispatchError, DispatchResult, Perbill, RuntimeDebug, TransactionOutcome,
+};
 use sp_std::{marker::PhantomData, prelude::*};
 
-use astar_primitives::{
-    ethereum_checked::{
-        AccountMapping, CheckedEthereumTransact, CheckedEthereumTx, EthereumTxInput,
-    },
-    xvm::{
-        CallFailure, CallOutput, CallResult, Context, FailureError::*, FailureRevert::*, VmId,
-        XvmCall,
-    },
-    Balance,
-};
+use astar_primitives::{
+    ethereum_checked::{AccountMapping, TryAccountMapping},
+    xvm::{
+        decode_evm_custom_error, into_dispatch_result, split_value, BatchCall, BatchOutput,
+        CallFailure, CallFrame, CallOutput, CallResult, Context, EvmCustomError,
+        EvmCustomErrorSignature, FailureError::*, FailureReason, FailureRevert, FailureRevert::*,
+        VmId, XvmCall, XvmCallSpec, XvmSimulationResult, XvmTarget,
+    },
+    Balance,
+};
+#[cfg(feature = "evm-vm")]
+use astar_primitives::{
+    ethereum_checked::{CheckedEthereumTransact, CheckedEthereumTx, EthereumTxInput},
+    xvm::{balance_to_u256, evm_call_status, ExitCategory},
+};
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+#[cfg(feature = "evm-tracing")]
+mod evm_tracing;
+
+pub mod runtime_api;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+pub mod weights;
+pub use weights::WeightInfo;
+
+mod mock;
+mod tests;
+
+pub use pallet::*;
+
+pub type WeightInfoOf<T> = <T as Config>::WeightInfo;
+
+environmental::thread_local_impl!(static IN_XVM: environmental::RefCell<bool> = environmental::RefCell::new(false));
+environmental::thread_local_impl!(static VM_PATH: environmental::RefCell<Option<Vec<VmId>>> = environmental::RefCell::new(None));
+environmental::thread_local_impl!(static TRACE_ID: environmental::RefCell<[u8; 16]> = environmental::RefCell::new([0; 16]));
+environmental::thread_local_impl!(static RELAXED_DETERMINISM: environmental::RefCell<bool> = environmental::RefCell::new(false));
+environmental::thread_local_impl!(static DEFERRED_DEPTH: environmental::RefCell<Option<u32>> = environmental::RefCell::new(None));
+environmental::thread_local_impl!(static CALL_GRAPH: environmental::RefCell<Option<CallGraph>> = environmental::RefCell::new(None));
+// Whether calls are executed by the no-op VM backend instead of their VM, see `noop_call`.
+#[cfg(any(test, feature = "noop-vm"))]
+environmental::thread_local_impl!(static NOOP_VM: environmental::RefCell<bool> = environmental::RefCell::new(false));
+
+/// Weight statistics of the calls executed in a VM, failed ones included.
+#[derive(PartialEq, Eq, Clone, Default, RuntimeDebug)]
+pub struct CallWeightStats {
+    /// Number of calls.
+    pub calls: u64,
+    /// Cumulative weight used by the calls.
+    pub total_weight: Weight,
+    /// Max weight used by a single call, for each weight component.
+    pub max_weight: Weight,
+}
+
+impl CallWeightStats {
+    /// Average weight used by a call, zero if there's no call.
+    pub fn average_weight(&self) -> Weight {
+        self.total_weight
+            .checked_div(self.calls)
+            .unwrap_or_default()
+    }
+}
+
+/// Handling of XVM calls whose value transfer would leave the target below the existential
+/// deposit.
+///
+/// `Allow` by default, the behavior of XVM before the policy. `Reject` and `TopUp` change the
+/// outcome of such calls, so using them is a runtime decision.
+#[derive(PartialEq, Eq, Clone, Copy, Default, RuntimeDebug)]
+pub enum ExistentialDepositPolicy {
+    /// Leave the transfer to the VM, which handles it as any transfer below the existential
+    /// deposit.
+    #[default]
+    Allow,
+    /// Fail the call with `BelowExistentialDeposit` before entering the VM.
+    Reject,
+    /// Transfer enough to bring the target to the existential deposit, which the caller must
+    /// afford.
+    TopUp,
+}
+
+/// A XVM call deferred to be executed after the current XVM frame unwinds.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct DeferredXvmCall<AccountId, BlockNumber> {
+    /// The VM of the caller, which must not be `vm_id`.
+    pub source_vm_id: VmId,
+    /// The VM Id of the target contract.
+    pub vm_id: VmId,
+    /// The caller account.
+    pub source: AccountId,
+    /// Target contract address.
+    pub target: Vec<u8>,
+    /// Call input data.
+    pub input: Vec<u8>,
+    /// Value to transfer.
+    pub value: Balance,
+    /// The last block the call can be executed in, it's dropped with a `DeferredCallExpired`
+    /// event afterwards. `None` for no deadline.
+    pub deadline: Option<BlockNumber>,
+    /// Number of deferred calls the call was scheduled from, `0` if not scheduled by a
+    /// deferred call. Set by `defer_call`.
+    pub depth: u32,
+}
+
+/// Calls recorded since a top-level call with `Context::record_call_graph`.
+#[derive(Default)]
+struct CallGraph {
+    /// Recorded calls, in the order they were made.
+    frames: Vec<CallFrame>,
+    /// Indices of the unfinished calls in `frames`, innermost last.
+    open: Vec<usize>,
+}
+
+impl CallGraph {
+    /// Record a new call, nested in the innermost unfinished one.
+    fn enter(&mut self, vm_id: VmId, source: Vec<u8>, target: Vec<u8>, value: Balance) {
+        self.open.push(self.frames.len());
+        self.frames.push(CallFrame {
+            depth: self.open.len().saturating_sub(1) as u32,
+            vm_id,
+            source,
+            target,
+            value,
+            used_weight: Weight::zero(),
+            failure: None,
+        });
+    }
+
+    /// Record the result of the innermost unfinished call.
+    fn exit(&mut self, result: &CallResult) {
+        let maybe_frame = self.open.pop().and_then(|index| self.frames.get_mut(index));
+        if let Some(frame) = maybe_frame {
+            match result {
+                Ok(output) => frame.used_weight = output.used_weight,
+                Err(failure) => {
+                    frame.used_weight = failure.used_weight;
+                    frame.failure = Some(failure.reason.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Guard of `IN_XVM`, which is set to `true` on entering and restored to `false` on drop,
+/// so it's restored on every return path, including unwinding from a panic.
+struct InXvmGuard;
+
+impl InXvmGuard {
+    /// Set `IN_XVM` to `true`, or `None` if it's already set.
+    fn enter() -> Option<Self> {
+        if IN_XVM.with(|in_xvm| in_xvm.replace(true)) {
+            None
+        } else {
+            Some(InXvmGuard)
+        }
+    }
+}
+
+impl Drop for InXvmGuard {
+    fn drop(&mut self) {
+        let _ = IN_XVM.with(|in_xvm| in_xvm.take());
+    }
+}
+
+/// Config of the WASM VM, `pallet_contracts::Config` if the `wasm-vm` feature is enabled.
+#[cfg(feature = "wasm-vm")]
+pub trait WasmVmConfig: pallet_contracts::Config {}
+#[cfg(feature = "wasm-vm")]
+impl<T: pallet_contracts::Config> WasmVmConfig for T {}
+
+/// Config of the WASM VM, empty as the `wasm-vm` feature is disabled.
+#[cfg(not(feature = "wasm-vm"))]
+pub trait WasmVmConfig {}
+#[cfg(not(feature = "wasm-vm"))]
+impl<T> WasmVmConfig for T {}
+
+/// Mapping from Ethereum gas to Substrate weight, `pallet_evm::GasWeightMapping` if the
+/// `evm-vm` feature is enabled.
+#[cfg(feature = "evm-vm")]
+pub trait EvmGasWeightMapping: GasWeightMapping {}
+#[cfg(feature = "evm-vm")]
+impl<T: GasWeightMapping> EvmGasWeightMapping for T {}
+
+/// Mapping from Ethereum gas to Substrate weight, empty as the `evm-vm` feature is disabled.
+#[cfg(not(feature = "evm-vm"))]
+pub trait EvmGasWeightMapping {}
+#[cfg(not(feature = "evm-vm"))]
+impl<T> EvmGasWeightMapping for T {}
+
+/// EVM transact of XVM calls into EVM, `CheckedEthereumTransact` if the `evm-vm` feature is
+/// enabled.
+#[cfg(feature = "evm-vm")]
+pub trait EvmTransact: CheckedEthereumTransact {}
+#[cfg(feature = "evm-vm")]
+impl<T: CheckedEthereumTransact> EvmTransact for T {}
+
+/// Helper to set up the state of the benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper {
+    /// Set the EVM balance of `address`, to pass the source balance check of calls into EVM.
+    fn set_evm_balance(address: H160, balance: Balance);
+}
+
+/// EVM transact of XVM calls into EVM, empty as the `evm-vm` feature is disabled.
+#[cfg(not(feature = "evm-vm"))]
+pub trait EvmTransact {}
+#[cfg(not(feature = "evm-vm"))]
+impl<T> EvmTransact for T {}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::*;
+    use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::*;
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config + WasmVmConfig {
+        /// The overarching event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// The currency to check the source balance against the call value, and to reserve
+        /// the staged input deposits.
+        type Currency: ReservableCurrency<Self::AccountId, Balance = Balance>;
+
+        /// The XVM pallet id, to derive the pallet's own account.
+        #[pallet::constant]
+        type PalletId: Get<PalletId>;
+
+        /// Mapping from `Account` to `H160`, consulted first.
+        ///
+        /// For instance explicitly registered mappings, the accounts without an entry are
+        /// mapped with `AccountMapping`.
+        type PrimaryAccountMapping: TryAccountMapping<Self::AccountId>;
+
+        /// Mapping from `Account` to `H160`, the fallback of `PrimaryAccountMapping`.
+        type AccountMapping: AccountMapping<Self::AccountId>;
+
+        /// Mapping from Ethereum gas to Substrate weight.
+        ///
+        /// Unused if the `evm-vm` feature is disabled, `()` can be used instead.
+        type GasWeightMapping: EvmGasWeightMapping;
+
+        /// `CheckedEthereumTransact` implementation.
+        ///
+        /// Unused if the `evm-vm` feature is disabled, `()` can be used instead.
+        type EthereumTransact: EvmTransact;
+
+        /// Max number of EVM access list entries, counting both addresses and storage keys.
+        #[pallet::constant]
+        type MaxAccessListEntries: Get<u32>;
+
+        /// Max output size of EVM calls.
+        #[pallet::constant]
+        type MaxEvmOutputSize: Get<u32>;
+
+        /// Max output size of WASM calls.
+        #[pallet::constant]
+        type MaxWasmOutputSize: Get<u32>;
+
+        /// Truncate output exceeding the max output size, instead of failing the call with
+        /// `OutputTooLarge`.
+        #[pallet::constant]
+        type TruncateOversizedOutput: Get<bool>;
+
+        /// Require an explicit gas limit for EVM calls, instead of converting it from the
+        /// weight limit.
+        #[pallet::constant]
+        type RequireExplicitGasLimit: Get<bool>;
+
+        /// Deny calls whose target is the caller's own counterpart in the target VM, i.e. the
+        /// mapped EVM address of the caller, or the caller account itself for WASM.
+        ///
+        /// Opt-in, as some patterns legitimately call their own counterpart.
+        #[pallet::constant]
+        type DenySelfCalls: Get<bool>;
+
+        /// Max value transferable in a single call, `None` for no limit.
+        ///
+        /// This is a safety rail against bugs in cross-VM bridges, not a substitute for
+        /// value checks in the contracts.
+        #[pallet::constant]
+        type MaxTransferValue: Get<Option<Balance>>;
+
+        /// Handling of value transfers which would leave the target below the existential
+        /// deposit, instead of failing or reaping the account inside the VM. See
+        /// `ExistentialDepositPolicy`, `Allow` keeps the VM behavior.
+        type TargetExistentialDepositPolicy: Get<ExistentialDepositPolicy>;
+
+        /// Accounts trusted to call EVM from an arbitrary source address via
+        /// `call_as_evm_address`.
+        ///
+        /// A trusted caller can impersonate any EVM account, including spending its balance,
+        /// so this should only contain privileged runtime accounts, if any.
+        type TrustedEvmSourceCallers: Contains<Self::AccountId>;
+
+        /// Accounts trusted to execute externally built EVM transactions via
+        /// `evm_transact_raw`.
+        ///
+        /// Like `TrustedEvmSourceCallers`, a trusted caller can transact from any EVM account.
+        type TrustedRawEvmTxCallers: Contains<Self::AccountId>;
+
+        /// Whether an address is a precompile, to flag simulated EVM calls touching
+        /// precompiles.
+        type IsPrecompile: Contains<H160>;
+
+        /// The EVM targets XVM calls are allowed into, other targets revert with
+        /// `TargetNotAllowed`. For instance, to only allow a curated set of precompiles.
+        type AllowedEvmTargets: Contains<H160>;
+
+        /// Known EVM custom errors, to decode the revert data of simulated EVM calls.
+        type EvmCustomErrors: Get<Vec<EvmCustomErrorSignature>>;
+
+        /// Mapping of EVM selectors to WASM message selectors, the leading 4 bytes of the
+        /// input of calls from EVM into WASM are rewritten accordingly.
+        ///
+        /// Inputs without a matching selector are passed through unchanged.
+        type SelectorMap: Get<Vec<([u8; 4], [u8; 4])>>;
+
+        /// Emit a single `XvmBatchExecuted` summary event at the end of `call_batch`,
+        /// instead of a `XvmCallExecuted` event per call.
+        #[pallet::constant]
+        type BatchSummaryEvent: Get<bool>;
+
+        /// Fraction of the weight limit above which the used weight of a call emits a
+        /// `WeightNearExhaustion` event, failed and successful calls alike. `None` to disable
+        /// the event.
+        #[pallet::constant]
+        type WeightExhaustionThreshold: Get<Option<Perbill>>;
+
+        /// Max number of calls in the deferred calls queue.
+        #[pallet::constant]
+        type MaxDeferredCalls: Get<u32>;
+
+        /// Max depth of deferred calls scheduled by deferred calls, so deferred calls can't
+        /// keep scheduling each other endlessly. Calls deferred beyond it are rejected with
+        /// a `DeferredScheduleRejected` event.
+        #[pallet::constant]
+        type MaxDeferredDepth: Get<u32>;
+
+        /// The origin allowed to pause and unpause XVM calls.
+        type PauseOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// Max size of the WASM call input staged by `stage_input`.
+        #[pallet::constant]
+        type MaxInputSize: Get<u32>;
+
+        /// Number of blocks a staged input is kept after its last chunk, before it expires.
+        #[pallet::constant]
+        type StagedInputLifetime: Get<BlockNumberFor<Self>>;
+
+        /// Deposit reserved per byte of staged input, released when the input is committed,
+        /// or replaced after it expired.
+        #[pallet::constant]
+        type StagedInputDepositPerByte: Get<Balance>;
+
+        /// Max number of XVM calls executed in a VM in a block, nested and deferred ones
+        /// included. Calls denied before entering the VM aren't counted.
+        ///
+        /// A coarse DoS mitigation complementing the weight limits, calls beyond it fail
+        /// with `CallLimitReached`.
+        #[pallet::constant]
+        type MaxCallsPerBlock: Get<u32>;
+
+        /// Max number of XVM calls to a target executed in a block, `None` if the target
+        /// isn't limited.
+        ///
+        /// Protects high-value contracts from being spammed via XVM, calls beyond it fail
+        /// with `TargetRateLimited`. Only the calls to limited targets are counted.
+        type MaxCallsPerTarget: Convert<XvmTarget<Self::AccountId>, Option<u32>>;
+
+        /// Weight information for extrinsics in this pallet.
+        type WeightInfo: WeightInfo;
+
+        /// Helper to set up the state of the benchmarks.
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: BenchmarkHelper;
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A call in a batch, or a deferred call, is executed.
+        XvmCallExecuted {
+            trace_id: [u8; 16],
+            vm_id: VmId,
+            success: bool,
+            used_weight: Weight,
+        },
+        /// A batch of calls is executed.
+        XvmBatchExecuted {
+            trace_id: [u8; 16],
+            count: u32,
+            successes: u32,
+            failures: u32,
+            total_weight: Weight,
+        },
+        /// XVM calls are paused.
+        Paused,
+        /// XVM calls are unpaused.
+        Unpaused,
+        /// A deferred call is dropped without being executed, as its deadline has passed.
+        DeferredCallExpired {
+            trace_id: [u8; 16],
+            vm_id: VmId,
+            deadline: BlockNumberFor<T>,
+        },
+        /// A call deferred by a deferred call is rejected, as its depth would exceed
+        /// `T::MaxDeferredDepth`.
+        DeferredScheduleRejected {
+            trace_id: [u8; 16],
+            vm_id: VmId,
+            depth: u32,
+        },
+        /// A call used more than `T::WeightExhaustionThreshold` of its weight limit.
+        WeightNearExhaustion {
+            trace_id: [u8; 16],
+            vm_id: VmId,
+            used: Weight,
+            limit: Weight,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The deferred calls queue is full.
+        TooManyDeferredCalls,
+        /// The deferred call would exceed `T::MaxDeferredDepth`.
+        DeferredScheduleTooDeep,
+        /// The staged input would exceed `T::MaxInputSize`.
+        StagedInputTooLarge,
+        /// No staged input, or it has expired.
+        NoStagedInput,
+        /// The XVM call failed with an error.
+        XvmCallFailed,
+        /// The XVM call reverted.
+        XvmCallReverted,
+    }
+
+    /// Calls deferred to be executed on idle, outside of the XVM frame they were queued in.
+    #[pallet::storage]
+    #[pallet::unbounded]
+    pub type DeferredCalls<T: Config> = StorageValue<
+        _,
+        BoundedVec<DeferredXvmCall<T::AccountId, BlockNumberFor<T>>, T::MaxDeferredCalls>,
+        ValueQuery,
+    >;
+
+    /// WASM call input staged in chunks by `stage_input`, with the block it expires at and
+    /// the reserved deposit.
+    #[pallet::storage]
+    pub type StagedInputs<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        (BoundedVec<u8, T::MaxInputSize>, BlockNumberFor<T>, Balance),
+    >;
+
+    /// Whether XVM calls are paused, all calls fail with `Paused` if set.
+    #[pallet::storage]
+    pub type XvmPaused<T> = StorageValue<_, bool, ValueQuery>;
+
+    /// Number of XVM calls executed in a VM in the current block, reset on initialize.
+    #[pallet::storage]
+    pub type CallsInBlock<T> = StorageValue<_, u32, ValueQuery>;
+
+    /// Number of XVM calls executed to a limited target, with the block they were executed
+    /// in. The counts of previous blocks are stale and read as zero, so the counters don't
+    /// need to be reset on initialize. See `calls_to_target_in_block`.
+    #[pallet::storage]
+    pub type CallsToTargetInBlock<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        XvmTarget<T::AccountId>,
+        (BlockNumberFor<T>, u32),
+        ValueQuery,
+    >;
+
+    #[pallet::genesis_config]
+    #[cfg_attr(feature = "std", derive(Default))]
+    pub struct GenesisConfig {
+        /// Whether XVM calls are paused from genesis, `false` by default.
+        pub paused: bool,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> GenesisBuild<T> for GenesisConfig {
+        fn build(&self) {
+            XvmPaused::<T>::put(self.paused);
+        }
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(_: BlockNumberFor<T>) -> Weight {
+            CallsInBlock::<T>::kill();
+            T::DbWeight::get().writes(1)
+        }
+
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Pallet::<T>::execute_deferred_calls(now, remaining_weight)
+        }
+    }
+
+    /// Number of calls executed in EVM, failed ones included.
+    #[pallet::storage]
+    pub type EvmCallCount<T> = StorageValue<_, u64, ValueQuery>;
+
+    /// Number of calls executed in WASM, failed ones included.
+    #[pallet::storage]
+    pub type WasmCallCount<T> = StorageValue<_, u64, ValueQuery>;
+
+    /// Number of calls failed in EVM or WASM execution.
+    #[pallet::storage]
+    pub type FailedCallCount<T> = StorageValue<_, u64, ValueQuery>;
+
+    /// Cumulative weight used by the calls executed in a VM, failed ones included.
+    #[pallet::storage]
+    pub type TotalCallWeight<T> = StorageMap<_, Twox64Concat, VmId, Weight, ValueQuery>;
+
+    /// Max weight used by a single call executed in a VM, for each weight component.
+    #[pallet::storage]
+    pub type MaxWeightObserved<T> = StorageMap<_, Twox64Concat, VmId, Weight, ValueQuery>;
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Pause or unpause XVM calls.
+        ///
+        /// This is a circuit breaker for security incidents. While paused, all XVM calls,
+        /// nested and deferred ones included, fail with `Paused`.
+        ///
+        /// The dispatch origin must be `T::PauseOrigin`.
+        #[pallet::call_index(0)]
+        #[pallet::weight(WeightInfoOf::<T>::set_paused())]
+        pub fn set_paused(origin: OriginFor<T>, paused: bool) -> DispatchResult {
+            T::PauseOrigin::ensure_origin(origin)?;
+
+            XvmPaused::<T>::put(paused);
+            if paused {
+                Self::deposit_event(Event::<T>::Paused);
+            } else {
+                Self::deposit_event(Event::<T>::Unpaused);
+            }
+            Ok(())
+        }
+
+        /// Append `chunk` to the WASM call input staged by the origin.
+        ///
+        /// This allows passing an input too large for a single extrinsic to a WASM contract,
+        /// via `commit_and_call`. The staged input expires `T::StagedInputLifetime` blocks
+        /// after its last chunk, in which case `chunk` starts a new input. The staged input
+        /// must not exceed `T::MaxInputSize`.
+        ///
+        /// `T::StagedInputDepositPerByte` is reserved for each byte of `chunk`. The deposit of
+        /// an expired input is released when `chunk` replaces it.
+        ///
+        /// The dispatch origin must be signed.
+        #[pallet::call_index(1)]
+        #[pallet::weight(WeightInfoOf::<T>::stage_input(chunk.len() as u32))]
+        pub fn stage_input(origin: OriginFor<T>, chunk: Vec<u8>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let now = frame_system::Pallet::<T>::block_number();
+            StagedInputs::<T>::try_mutate(&who, |maybe_staged| -> DispatchResult {
+                let (mut input, deposit) = match maybe_staged.take() {
+                    Some((input, expires_at, deposit)) if now <= expires_at => {
+                        (input.into_inner(), deposit)
+                    }
+                    Some((_, _, deposit)) => {
+                        <T as Config>::Currency::unreserve(&who, deposit);
+                        (Vec::new(), 0)
+                    }
+                    None => (Vec::new(), 0),
+                };
+                let chunk_deposit =
+                    T::StagedInputDepositPerByte::get().saturating_mul(chunk.len() as Balance);
+                input.extend(chunk);
+                let input =
+                    BoundedVec::try_from(input).map_err(|_| Error::<T>::StagedInputTooLarge)?;
+                <T as Config>::Currency::reserve(&who, chunk_deposit)?;
+                *maybe_staged = Some((
+                    input,
+                    now.saturating_add(T::StagedInputLifetime::get()),
+                    deposit.saturating_add(chunk_deposit),
+                ));
+                Ok(())
+            })
+        }
+
+        /// Call the WASM contract `target` with the input staged by the origin.
+        ///
+        /// The staged input is consumed and its deposit released if the call succeeds, and they
+        /// are kept otherwise so the call can be retried. The call is made from the origin,
+        /// which isn't in any VM: it isn't denied as a same-VM call, and unlike calls from EVM
+        /// its input isn't translated with `T::SelectorMap`. `weight_limit` is the context
+        /// weight limit.
+        ///
+        /// The dispatch origin must be signed.
+        #[pallet::call_index(2)]
+        #[pallet::weight(
+            WeightInfoOf::<T>::commit_and_call()
+                .saturating_add(weights::call_overheads::<WeightInfoOf<T>>(VmId::Wasm))
+                .saturating_add(*weight_limit)
+        )]
+        pub fn commit_and_call(
+            origin: OriginFor<T>,
+            target: Vec<u8>,
+            value: Balance,
+            weight_limit: Weight,
+            storage_deposit_limit: Option<Balance>,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let now = frame_system::Pallet::<T>::block_number();
+            let input = match StagedInputs::<T>::take(&who) {
+                Some((input, expires_at, deposit)) if now <= expires_at => {
+                    <T as Config>::Currency::unreserve(&who, deposit);
+                    input
+                }
+                _ => return Err(Error::<T>::NoStagedInput.into()),
+            };
+
+            let context = Context {
+                source_vm_id: VmId::Wasm,
+                weight_limit,
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: true,
+                record_call_graph: false,
+            };
+            let result = Self::do_call(
+                context,
+                VmId::Wasm,
+                who,
+                target,
+                input.into_inner(),
+                value,
+                storage_deposit_limit,
+                None,
+                None,
+                None,
+                false,
+                true,
+            );
+
+            // The used weight of the call, overheads included, on top of taking the staged input.
+            let commit_weight = WeightInfoOf::<T>::commit_and_call();
+            let with_commit_weight = |post_info: PostDispatchInfo| PostDispatchInfo {
+                actual_weight: post_info
+                    .actual_weight
+                    .map(|weight| weight.saturating_add(commit_weight)),
+                ..post_info
+            };
+            into_dispatch_result(
+                result,
+                Error::<T>::XvmCallFailed,
+                Error::<T>::XvmCallReverted,
+            )
+            .map(with_commit_weight)
+            .map_err(|e| DispatchErrorWithPostInfo {
+                post_info: with_commit_weight(e.post_info),
+                error: e.error,
+            })
+        }
+    }
+}
+
+impl<T: Config> XvmCall<T::AccountId> for Pallet<T> {
+    fn call(
+        context: Context,
+        vm_id: VmId,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        storage_deposit_limit: Option<Balance>,
+    ) -> CallResult {
+        Pallet::<T>::do_call(
+            context,
+            vm_id,
+            source,
+            target,
+            input,
+            value,
+            storage_deposit_limit,
+            None,
+            None,
+            None,
+            false,
+            false,
+        )
+    }
+
+    fn call_with_dry_run(
+        context: Context,
+        vm_id: VmId,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        storage_deposit_limit: Option<Balance>,
+        dry_run: bool,
+    ) -> CallResult {
+        Pallet::<T>::do_call(
+            context,
+            vm_id,
+            source,
+            target,
+            input,
+            value,
+            storage_deposit_limit,
+            None,
+            None,
+            None,
+            dry_run,
+            false,
+        )
+    }
+
+    /// Call overheads of `vm_id` with the runtime weights.
+    fn call_overheads(vm_id: VmId) -> Weight {
+        weights::call_overheads::<WeightInfoOf<T>>(vm_id)
+    }
+}
+
+impl<T: Config> Pallet<T> {
+    /// Whether currently executing inside a XVM call.
+    pub fn is_in_xvm() -> bool {
+        IN_XVM.with(|in_xvm| *in_xvm.borrow())
+    }
+
+    /// The `H160` address XVM calls into EVM from `account` are executed from.
+    ///
+    /// Mapped with `T::PrimaryAccountMapping` if `account` has an entry, otherwise with
+    /// `T::AccountMapping`. The mapping is one-way, neither has an inverse.
+    pub fn mapped_evm_address(account: T::AccountId) -> H160 {
+        T::PrimaryAccountMapping::try_into_h160(&account)
+            .unwrap_or_else(|| T::AccountMapping::into_h160(account))
+    }
+
+    /// The weight limit for an EVM `gas_limit`, for callers setting `context.weight_limit`
+    /// in gas.
+    ///
+    /// Converted with `T::GasWeightMapping`, saturating at the max block weight.
+    #[cfg(feature = "evm-vm")]
+    pub fn weight_limit_from_gas(gas_limit: U256) -> Weight {
+        let max_block = T::BlockWeights::get().max_block;
+        let gas_limit = u64::try_from(gas_limit)
+            .unwrap_or(u64::MAX)
+            .min(T::GasWeightMapping::weight_to_gas(max_block));
+        T::GasWeightMapping::gas_to_weight(gas_limit, true).min(max_block)
+    }
+
+    /// The EVM nonce of `source`.
+    ///
+    /// XVM calls into EVM are executed from `mapped_evm_address(source)`, so this
+    /// is the nonce of the mapped `H160` address. If the mapping of `source` changes,
+    /// for instance an EVM address is claimed, the nonce of the new address is returned.
+    #[cfg(feature = "evm-vm")]
+    pub fn evm_nonce(source: T::AccountId) -> U256 {
+        T::EthereumTransact::account_nonce(Self::mapped_evm_address(source))
+    }
+
+    /// Call an EVM contract, optionally asserting the EVM nonce of `source`.
+    ///
+    /// If `expected_nonce` is `Some` and it doesn't match `Self::evm_nonce(source)`,
+    /// the call fails with `NonceMismatch` before entering the EVM. Each successful
+    /// EVM execution increases the nonce, so multiple XVM calls in the same block from
+    /// the same source can be ordered and can't be replayed by mistake.
+    #[cfg(feature = "evm-vm")]
+    pub fn call_with_nonce(
+        context: Context,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        expected_nonce: Option<U256>,
+    ) -> CallResult {
+        if let Some(expected_nonce) = expected_nonce {
+            let nonce = Self::evm_nonce(source.clone());
+            ensure!(
+                nonce == expected_nonce,
+                CallFailure::error(
+                    NonceMismatch,
+                    Self::call_overheads(VmId::Evm).saturating_add(T::DbWeight::get().reads(1)),
+                )
+            );
+        }
+
+        Self::do_call(
+            context,
+            VmId::Evm,
+            source,
+            target,
+            input,
+            value,
+            None,
+            None,
+            None,
+            None,
+            false,
+            false,
+        )
+    }
+
+    /// Call an EVM contract with an EIP-2930 access list.
+    ///
+    /// The total number of addresses and storage keys in `access_list` must not exceed
+    /// `T::MaxAccessListEntries`, otherwise the call fails with `AccessListTooLarge`.
+    pub fn call_with_access_list(
+        context: Context,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        access_list: Vec<(H160, Vec<H256>)>,
+    ) -> CallResult {
+        Self::do_call(
+            context,
+            VmId::Evm,
+            source,
+            target,
+            input,
+            value,
+            None,
+            Some(access_list),
+            None,
+            None,
+            false,
+            false,
+        )
+    }
+
+    /// Call an EVM contract with an explicit gas limit.
+    ///
+    /// By default the gas limit is converted from `context.weight_limit`. With an explicit
+    /// `gas_limit`, the lower of the two is used. If `T::RequireExplicitGasLimit` is `true`,
+    /// EVM calls without an explicit gas limit fail with `GasLimitRequired`.
+    pub fn call_with_gas_limit(
+        context: Context,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        gas_limit: u64,
+    ) -> CallResult {
+        Self::do_call(
+            context,
+            VmId::Evm,
+            source,
+            target,
+            input,
+            value,
+            None,
+            None,
+            Some(gas_limit),
+            None,
+            false,
+            false,
+        )
+    }
+
+    /// Call an EVM contract from an arbitrary `source` address.
+    ///
+    /// Unlike `call`, the EVM source isn't mapped from an account, so `source` can be any
+    /// address, including one no account maps to. This allows impersonating any EVM
+    /// account, so only callers in `T::TrustedEvmSourceCallers` may use it; for any other
+    /// `caller` the call fails with `UntrustedCaller`. `caller` must be the authenticated
+    /// origin of the call, never a value taken from call input. The value is transferred from
+    /// `source`.
+    pub fn call_as_evm_address(
+        context: Context,
+        caller: T::AccountId,
+        source: H160,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+    ) -> CallResult {
+        ensure!(
+            T::TrustedEvmSourceCallers::contains(&caller),
+            CallFailure::denied(
+                UntrustedCaller,
+                Self::call_overheads(VmId::Evm),
+                WeightInfoOf::<T>::call_denied_overheads(),
+            )
+        );
+
+        Self::do_call(
+            context,
+            VmId::Evm,
+            caller,
+            target,
+            input,
+            value,
+            None,
+            None,
+            None,
+            Some(source),
+            false,
+            false,
+        )
+    }
+
+    /// Execute an externally built EVM transaction `tx` from `source`.
+    ///
+    /// Unlike `call_as_evm_address`, XVM doesn't build the transaction: the input isn't
+    /// bounded and the target isn't decoded, `tx` is only checked against the weight limit,
+    /// the max access list entries and the allowed EVM targets. Only callers in
+    /// `T::TrustedRawEvmTxCallers` may use it, for any other `caller` the call fails with
+    /// `UntrustedCaller`. Otherwise, it's executed as any XVM call from `source`: the
+    /// overheads are charged, reentrance is denied, the call limits and value checks apply
+    /// and the call is counted.
+    #[cfg(feature = "evm-vm")]
+    pub fn evm_transact_raw(
+        context: Context,
+        caller: T::AccountId,
+        source: H160,
+        tx: CheckedEthereumTx,
+    ) -> CallResult {
+        let value: Balance = tx.value.unique_saturated_into();
+        Self::record_call_frame(
+            context.record_call_graph,
+            VmId::Evm,
+            caller.encode(),
+            tx.target.encode(),
+            value,
+            || Self::execute_raw_evm_tx(context, caller, source, tx),
+        )
+    }
+
+    #[cfg(feature = "evm-vm")]
+    fn execute_raw_evm_tx(
+        mut context: Context,
+        caller: T::AccountId,
+        source: H160,
+        mut tx: CheckedEthereumTx,
+    ) -> CallResult {
+        // Nested calls inherit the trace id of the outermost call.
+        if Self::is_in_xvm() {
+            context.trace_id = TRACE_ID.with(|trace_id| *trace_id.borrow());
+        }
+        Self::record_vm_path(VmId::Evm);
+
+        let overheads = Self::call_overheads(VmId::Evm);
+        let denial_overheads = WeightInfoOf::<T>::call_denied_overheads();
+        ensure!(
+            T::TrustedRawEvmTxCallers::contains(&caller),
+            CallFailure::denied(UntrustedCaller, overheads, denial_overheads)
+        );
+        ensure!(
+            !XvmPaused::<T>::get(),
+            CallFailure::error(Paused, overheads)
+        );
+        ensure!(
+            CallsInBlock::<T>::get() < T::MaxCallsPerBlock::get(),
+            CallFailure::error(CallLimitReached, overheads)
+        );
+
+        let weight_limit = context.weight_limit.saturating_sub(overheads);
+        ensure!(
+            tx.gas_limit <= U256::from(T::GasWeightMapping::weight_to_gas(weight_limit)),
+            CallFailure::revert(GasLimitTooHigh, overheads)
+        );
+        Self::ensure_access_list_bounded(&tx.maybe_access_list, overheads)?;
+        ensure!(
+            T::AllowedEvmTargets::contains(&tx.target),
+            CallFailure::revert(TargetNotAllowed, overheads)
+        );
+
+        let in_xvm_guard = InXvmGuard::enter()
+            .ok_or_else(|| CallFailure::denied(ReentranceDenied, overheads, denial_overheads))?;
+
+        let target = XvmTarget::Evm(tx.target);
+        if let Some(max_calls) = T::MaxCallsPerTarget::convert(target.clone()) {
+            ensure!(
+                Self::calls_to_target_in_block(&target) < max_calls,
+                CallFailure::error(TargetRateLimited, overheads)
+            );
+        }
+
+        // The same value checks as any XVM call into EVM from `source`.
+        let mut checks_weight = denial_overheads;
+        let value = Self::ensure_transfer_allowed(
+            &caller,
+            Some(source),
+            &target,
+            tx.value.unique_saturated_into(),
+            overheads,
+            &mut checks_weight,
+        )?;
+        // Only set if topped up, to keep values above the max balance as is.
+        if balance_to_u256(value) > tx.value {
+            tx.value = balance_to_u256(value);
+        }
+
+        TRACE_ID.with(|trace_id| *trace_id.borrow_mut() = context.trace_id);
+        let (trace_id, call_weight_limit) = (context.trace_id, context.weight_limit);
+
+        let res = Self::revertible(OutputTooLarge, || {
+            Self::evm_transact(context, source, tx, overheads, weight_limit)
+                .and_then(|output| Self::bound_output(VmId::Evm, output))
+        });
+        let res = Self::count_call(target, res);
+
+        drop(in_xvm_guard);
+
+        Self::check_weight_exhaustion(trace_id, VmId::Evm, &res, call_weight_limit);
+        res
+    }
+
+    /// Call a contract, and validate the output with `is_valid`.
+    ///
+    /// If the output isn't valid, the call fails with `OutputDecodeFailed` and its state
+    /// changes are reverted. `astar_primitives::xvm::{scale_decodable, abi_decodable}` can be
+    /// used as validators.
+    pub fn call_with_output_validation(
+        context: Context,
+        vm_id: VmId,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        storage_deposit_limit: Option<Balance>,
+        is_valid: impl FnOnce(&[u8]) -> bool,
+    ) -> CallResult {
+        Self::revertible(OutputDecodeFailed, || {
+            Self::do_call(
+                context,
+                vm_id,
+                source,
+                target,
+                input,
+                value,
+                storage_deposit_limit,
+                None,
+                None,
+                None,
+                false,
+                false,
+            )
+            .and_then(|output| output.validated(is_valid))
+        })
+    }
+
+    /// Execute a batch of calls from `source`, in order.
+    ///
+    /// All calls share `context.weight_limit`, each call is given the weight limit left by
+    /// the previous ones. A failed call doesn't stop the rest of the batch.
+    ///
+    /// Returns the results keyed by the index of the call in the batch, and the total weight
+    /// of the batch: `WeightInfo::batch_call_overheads` plus the weight used by the calls. The
+    /// batch overheads are taken from the weight limit before the first call.
+    pub fn call_batch(
+        context: Context,
+        source: T::AccountId,
+        calls: Vec<BatchCall>,
+    ) -> BatchOutput {
+        let summary_event = T::BatchSummaryEvent::get();
+        let batch_overheads = WeightInfoOf::<T>::batch_call_overheads();
+        let mut weight_left = context.weight_limit.saturating_sub(batch_overheads);
+        let mut total_weight = batch_overheads;
+        let mut successes: u32 = 0;
+
+        let results: Vec<(usize, CallResult)> = calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| {
+                let call_context = Context {
+                    weight_limit: weight_left,
+                    ..context.clone()
+                };
+                let vm_id = call.vm_id;
+                let result = Self::do_call(
+                    call_context,
+                    vm_id,
+                    source.clone(),
+                    call.target,
+                    call.input,
+                    call.value,
+                    call.storage_deposit_limit,
+                    None,
+                    None,
+                    None,
+                    false,
+                    false,
+                );
+
+                let used_weight = match &result {
+                    Ok(output) => output.used_weight,
+                    Err(failure) => failure.used_weight,
+                };
+                weight_left = weight_left.saturating_sub(used_weight);
+                total_weight.saturating_accrue(used_weight);
+                if result.is_ok() {
+                    successes = successes.saturating_add(1);
+                }
+
+                if !summary_event {
+                    Self::deposit_event(Event::<T>::XvmCallExecuted {
+                        trace_id: context.trace_id,
+                        vm_id,
+                        success: result.is_ok(),
+                        used_weight,
+                    });
+                }
+                (index, result)
+            })
+            .collect();
+
+        if summary_event {
+            let count = results.len() as u32;
+            Self::deposit_event(Event::<T>::XvmBatchExecuted {
+                trace_id: context.trace_id,
+                count,
+                successes,
+                failures: count.saturating_sub(successes),
+                total_weight,
+            });
+        }
+
+        BatchOutput {
+            results,
+            total_weight,
+        }
+    }
+
+    /// Execute a batch of calls from `source` like `call_batch`, splitting `value` between
+    /// the calls proportionally to their weights.
+    ///
+    /// The `value` of each call is replaced by its share of `value`, see `split_value` for
+    /// the rounding. Calls with a zero weight transfer no value.
+    pub fn call_batch_with_split_value(
+        context: Context,
+        source: T::AccountId,
+        value: Balance,
+        calls: Vec<(BatchCall, u32)>,
+    ) -> BatchOutput {
+        let weights: Vec<u32> = calls.iter().map(|(_, weight)| *weight).collect();
+        let calls = calls
+            .into_iter()
+            .zip(split_value(value, &weights))
+            .map(|((call, _), value)| BatchCall { value, ..call })
+            .collect();
+        Self::call_batch(context, source, calls)
+    }
+
+    /// Execute independent calls from `source`, in order.
+    ///
+    /// Unlike `call_batch`, each call has its own context, and so its own weight limit, and the
+    /// overheads apply per call. A failed call doesn't stop the rest, all results are returned.
+    ///
+    /// Reentrance is denied for the whole operation: each call holds the reentrance guard, and
+    /// if already in a XVM call, all calls fail with `ReentranceDenied`.
+    pub fn call_many(source: T::AccountId, calls: Vec<XvmCallSpec>) -> Vec<CallResult> {
+        calls
+            .into_iter()
+            .map(|call| Self::call_spec(source.clone(), call))
+            .collect()
+    }
+
+    /// Execute the `primary` call from `source`, then `on_success` if it succeeded, or
+    /// `on_failure` if it failed.
+    ///
+    /// Returns the results of the primary call and of the follow-up call, `None` if there's
+    /// none for the primary call result. The used weight of the operation is the sum of both.
+    /// As with `call_many`, each call has its own context, and reentrance is denied for the
+    /// whole operation.
+    pub fn call_conditional(
+        source: T::AccountId,
+        primary: XvmCallSpec,
+        on_success: Option<XvmCallSpec>,
+        on_failure: Option<XvmCallSpec>,
+    ) -> (CallResult, Option<CallResult>) {
+        let primary_result = Self::call_spec(source.clone(), primary);
+        let follow_up = if primary_result.is_ok() {
+            on_success
+        } else {
+            on_failure
+        };
+        let follow_up_result = follow_up.map(|call| Self::call_spec(source, call));
+        (primary_result, follow_up_result)
+    }
+
+    fn call_spec(source: T::AccountId, call: XvmCallSpec) -> CallResult {
+        Self::do_call(
+            call.context,
+            call.vm_id,
+            source,
+            call.target,
+            call.input,
+            call.value,
+            call.storage_deposit_limit,
+            None,
+            None,
+            None,
+            false,
+            false,
+        )
+    }
 
-#[cfg(feature = "runtime-benchmarks")]
-mod benchmarking;
+    /// Defer `call` to be executed on idle, after the current XVM frame unwinds, so it's not
+    /// denied as reentrance.
+    ///
+    /// Fails with `TooManyDeferredCalls` if the queue already has `T::MaxDeferredCalls` calls.
+    /// Deferred calls are executed in order, the ones not fitting in the idle weight of the
+    /// block are kept for the next blocks, until their deadline if any. Results are only
+    /// reported by `XvmCallExecuted` and `DeferredCallExpired` events.
+    ///
+    /// `call.depth` is set from the deferred call being executed, if any. Beyond
+    /// `T::MaxDeferredDepth`, the call is rejected with `DeferredScheduleTooDeep`.
+    pub fn defer_call(
+        mut call: DeferredXvmCall<T::AccountId, BlockNumberFor<T>>,
+    ) -> DispatchResult {
+        call.depth = DEFERRED_DEPTH
+            .with(|depth| *depth.borrow())
+            .map_or(0, |depth| depth.saturating_add(1));
+        if call.depth > T::MaxDeferredDepth::get() {
+            // The trace id of the call scheduling it, if in XVM.
+            let trace_id = if Self::is_in_xvm() {
+                TRACE_ID.with(|trace_id| *trace_id.borrow())
+            } else {
+                [0; 16]
+            };
+            Self::deposit_event(Event::<T>::DeferredScheduleRejected {
+                trace_id,
+                vm_id: call.vm_id,
+                depth: call.depth,
+            });
+            return Err(Error::<T>::DeferredScheduleTooDeep.into());
+        }
 
-pub mod weights;
-pub use weights::WeightInfo;
+        DeferredCalls::<T>::try_mutate(|calls| {
+            calls
+                .try_push(call)
+                .map_err(|_| Error::<T>::TooManyDeferredCalls.into())
+        })
+    }
 
-mod mock;
-mod tests;
+    /// Execute the deferred calls within `remaining_weight`, returns the used weight.
+    ///
+    /// Calls whose deadline is before `now` are dropped instead.
+    fn execute_deferred_calls(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+        let mut used_weight = T::DbWeight::get().reads(1);
+        if remaining_weight.any_lt(used_weight) {
+            return Weight::zero();
+        }
+        let calls = DeferredCalls::<T>::get().into_inner();
+        if calls.is_empty() {
+            return used_weight;
+        }
+        used_weight.saturating_accrue(T::DbWeight::get().writes(1));
 
-pub use pallet::*;
+        let mut executed = 0;
+        for call in &calls {
+            if let Some(deadline) = call.deadline.filter(|deadline| *deadline < now) {
+                executed += 1;
+                Self::deposit_event(Event::<T>::DeferredCallExpired {
+                    trace_id: [0; 16],
+                    vm_id: call.vm_id,
+                    deadline,
+                });
+                continue;
+            }
 
-pub type WeightInfoOf<T> = <T as Config>::WeightInfo;
+            let weight_limit = remaining_weight.saturating_sub(used_weight);
+            let overheads = Self::call_overheads(call.vm_id);
+            if weight_limit.any_lt(overheads) {
+                break;
+            }
 
-environmental::thread_local_impl!(static IN_XVM: environmental::RefCell<bool> = environmental::RefCell::new(false));
+            let context = Context {
+                source_vm_id: call.source_vm_id,
+                weight_limit,
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: true,
+                record_call_graph: false,
+            };
+            // Calls deferred by the call are one level deeper.
+            DEFERRED_DEPTH.with(|depth| depth.replace(Some(call.depth)));
+            let result = Self::do_call(
+                context,
+                call.vm_id,
+                call.source.clone(),
+                call.target.clone(),
+                call.input.clone(),
+                call.value,
+                None,
+                None,
+                None,
+                None,
+                false,
+                false,
+            );
+            DEFERRED_DEPTH.with(|depth| depth.take());
+            let call_weight = match &result {
+                Ok(output) => output.used_weight,
+                Err(failure) => failure.used_weight,
+            };
+            used_weight.saturating_accrue(call_weight);
+            executed += 1;
 
-#[frame_support::pallet]
-pub mod pallet {
-    use super::*;
+            Self::deposit_event(Event::<T>::XvmCallExecuted {
+                trace_id: [0; 16],
+                vm_id: call.vm_id,
+                success: result.is_ok(),
+                used_weight: call_weight,
+            });
+        }
 
-    #[pallet::pallet]
-    pub struct Pallet<T>(PhantomData<T>);
+        // Keep the calls deferred by the executed ones, queued after the others.
+        DeferredCalls::<T>::mutate(|queue| {
+            let mut queue_calls = sp_std::mem::take(queue).into_inner();
+            queue_calls.drain(..executed.min(queue_calls.len()));
+            *queue = BoundedVec::truncate_from(queue_calls);
+        });
+        used_weight
+    }
 
-    #[pallet::config]
-    pub trait Config: frame_system::Config + pallet_contracts::Config {
-        /// Mapping from `Account` to `H160`.
-        type AccountMapping: AccountMapping<Self::AccountId>;
+    /// Simulate an XVM call without committing any state changes.
+    ///
+    /// Intended for off-chain use via `XvmRuntimeApi`, all storage changes made by the call
+    /// are rolled back. The VM transitions of the call and its nested calls are returned as
+    /// `vm_transition_path`.
+    pub fn simulate_call(
+        context: Context,
+        vm_id: VmId,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+    ) -> XvmSimulationResult {
+        Self::simulate_call_with_determinism(context, vm_id, source, target, input, value, false)
+    }
 
-        /// Mapping from Ethereum gas to Substrate weight.
-        type GasWeightMapping: GasWeightMapping;
+    /// Simulate an XVM call like `simulate_call`, with WASM calls executed with
+    /// `Determinism::Relaxed` if `relaxed_determinism`, for contracts using non-deterministic
+    /// host functions.
+    ///
+    /// On-chain calls always enforce determinism, see `wasm_determinism`.
+    pub fn simulate_call_with_determinism(
+        context: Context,
+        vm_id: VmId,
+        source: T::AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        relaxed_determinism: bool,
+    ) -> XvmSimulationResult {
+        let (source_vm_id, weight_limit) = (context.source_vm_id, context.weight_limit);
+        let simulate = || {
+            let result = with_transaction(|| {
+                let result = Self::do_call(
+                    context, vm_id, source, target, input, value, None, None, None, None, false,
+                    false,
+                );
+                TransactionOutcome::Rollback(Ok::<_, DispatchError>(result))
+            });
+            // Only fails if the transactional layer limit is exceeded.
+            result.unwrap_or_else(|e| {
+                let reason: &str = e.into();
+                Err(CallFailure::error(VmError(reason.into()), Weight::zero()))
+            })
+        };
 
-        /// `CheckedEthereumTransact` implementation.
-        type EthereumTransact: CheckedEthereumTransact;
+        // Record the VM transitions of the call and its nested calls.
+        VM_PATH.with(|path| path.replace(Some(vec![source_vm_id])));
+        RELAXED_DETERMINISM.with(|relaxed| relaxed.replace(relaxed_determinism));
 
-        /// Weight information for extrinsics in this pallet.
-        type WeightInfo: WeightInfo;
+        #[cfg(feature = "evm-tracing")]
+        let result = if vm_id == VmId::Evm {
+            let (result, trace) = evm_tracing::trace(simulate);
+            let mut result = XvmSimulationResult::from(result);
+            result.top_level_gas_used = Some(trace.top_level_gas_used);
+            result.touched_precompile = Some(
+                trace
+                    .called_addresses
+                    .iter()
+                    .any(|address| T::IsPrecompile::contains(address)),
+            );
+            result
+        } else {
+            simulate().into()
+        };
+        #[cfg(not(feature = "evm-tracing"))]
+        let result = XvmSimulationResult::from(simulate());
+
+        let custom_error = match result.failure {
+            Some(FailureReason::Revert(VmRevert(_))) if vm_id == VmId::Evm => {
+                Self::decode_evm_custom_error(&result.output)
+            }
+            _ => None,
+        };
+        let weight_diagnostic = match result.failure {
+            Some(FailureReason::Error(OutOfGas)) => Some(Self::weight_diagnostic(
+                vm_id,
+                result.used_weight,
+                weight_limit,
+            )),
+            _ => None,
+        };
+        RELAXED_DETERMINISM.with(|relaxed| relaxed.take());
+        XvmSimulationResult {
+            custom_error,
+            weight_diagnostic,
+            vm_transition_path: VM_PATH.with(|path| path.take()).unwrap_or_default(),
+            call_graph: CALL_GRAPH
+                .with(|graph| graph.take())
+                .map(|graph| graph.frames)
+                .unwrap_or_default(),
+            ..result
+        }
     }
-}
 
-impl<T> XvmCall<T::AccountId> for Pallet<T>
-where
-    T: Config,
-    T::Currency: Currency<T::AccountId, Balance = Balance>,
-{
-    fn call(
+    /// The determinism of WASM calls, `Determinism::Relaxed` only while simulating with relaxed
+    /// determinism.
+    ///
+    /// Determinism is enforced for on-chain calls regardless of the relaxed setting, for
+    /// consensus safety.
+    #[cfg(feature = "wasm-vm")]
+    fn wasm_determinism() -> Determinism {
+        if Self::is_simulating() && RELAXED_DETERMINISM.with(|relaxed| *relaxed.borrow()) {
+            Determinism::Relaxed
+        } else {
+            Determinism::Enforced
+        }
+    }
+
+    /// Decode EVM revert data as one of the known custom errors in `T::EvmCustomErrors`.
+    pub fn decode_evm_custom_error(data: &[u8]) -> Option<EvmCustomError> {
+        decode_evm_custom_error(data, &T::EvmCustomErrors::get())
+    }
+
+    /// Rewrite the leading EVM selector of `input` to the WASM one in `T::SelectorMap`, if any.
+    pub fn translate_selector(mut input: Vec<u8>) -> Vec<u8> {
+        if input.len() < 4 {
+            return input;
+        }
+        if let Some((_, wasm_selector)) = T::SelectorMap::get()
+            .into_iter()
+            .find(|(evm_selector, _)| evm_selector[..] == input[..4])
+        {
+            input[..4].copy_from_slice(&wasm_selector);
+        }
+        input
+    }
+
+    /// Execute a call with `execute_call`, recorded as a `CallFrame` if a call graph is being
+    /// recorded.
+    ///
+    /// `native_source` is set for calls from a signed account outside of any VM, which have no
+    /// source VM and so are never same-VM calls. Only the call made by `commit_and_call` is,
+    /// nested calls are from the VM they're made in.
+    fn do_call(
         context: Context,
         vm_id: VmId,
         source: T::AccountId,
@@ -111,142 +1535,558 @@ where
         input: Vec<u8>,
         value: Balance,
         storage_deposit_limit: Option<Balance>,
+        maybe_access_list: Option<Vec<(H160, Vec<H256>)>>,
+        gas_limit_override: Option<u64>,
+        evm_source_override: Option<H160>,
+        skip_execution: bool,
+        native_source: bool,
     ) -> CallResult {
-        Pallet::<T>::do_call(
-            context,
+        let (record_call_graph, frame_source) = (context.record_call_graph, source.encode());
+        Self::record_call_frame(
+            record_call_graph,
             vm_id,
-            source,
-            target,
-            input,
+            frame_source,
+            target.clone(),
             value,
-            storage_deposit_limit,
-            false,
+            || {
+                Self::execute_call(
+                    context,
+                    vm_id,
+                    source,
+                    target,
+                    input,
+                    value,
+                    storage_deposit_limit,
+                    maybe_access_list,
+                    gas_limit_override,
+                    evm_source_override,
+                    skip_execution,
+                    native_source,
+                )
+            },
         )
     }
-}
 
-impl<T> Pallet<T>
-where
-    T: Config,
-    T::Currency: Currency<T::AccountId, Balance = Balance>,
-{
-    fn do_call(
-        context: Context,
+    /// Execute `call`, recorded as a `CallFrame` from `source` to `target` in `vm_id` if a
+    /// call graph is being recorded.
+    ///
+    /// A top-level call starts a new call graph if `record_call_graph` is set while simulating,
+    /// and clears the previous one otherwise. On-chain calls are never recorded.
+    fn record_call_frame(
+        record_call_graph: bool,
+        vm_id: VmId,
+        source: Vec<u8>,
+        target: Vec<u8>,
+        value: Balance,
+        call: impl FnOnce() -> CallResult,
+    ) -> CallResult {
+        if !Self::is_in_xvm() {
+            let call_graph = (record_call_graph && Self::is_simulating()).then(CallGraph::default);
+            CALL_GRAPH.with(|graph| graph.replace(call_graph));
+        }
+        let recording = CALL_GRAPH.with(|graph| match graph.borrow_mut().as_mut() {
+            Some(graph) => {
+                graph.enter(vm_id, source, target, value);
+                true
+            }
+            None => false,
+        });
+
+        let result = call();
+
+        if recording {
+            CALL_GRAPH.with(|graph| {
+                if let Some(graph) = graph.borrow_mut().as_mut() {
+                    graph.exit(&result);
+                }
+            });
+        }
+        result
+    }
+
+    /// Whether a call is being simulated by `simulate_call_with_determinism`.
+    fn is_simulating() -> bool {
+        VM_PATH.with(|path| path.borrow().is_some())
+    }
+
+    /// Record the transition to `vm_id` if simulating, denied transitions included.
+    fn record_vm_path(vm_id: VmId) {
+        VM_PATH.with(|path| {
+            if let Some(path) = path.borrow_mut().as_mut() {
+                path.push(vm_id);
+            }
+        });
+    }
+
+    /// Emit `WeightNearExhaustion` if the call used more than `T::WeightExhaustionThreshold`
+    /// of its `weight_limit`.
+    fn check_weight_exhaustion(
+        trace_id: [u8; 16],
+        vm_id: VmId,
+        result: &CallResult,
+        weight_limit: Weight,
+    ) {
+        if let Some(threshold) = T::WeightExhaustionThreshold::get() {
+            let used = match result {
+                Ok(output) => output.used_weight,
+                Err(failure) => failure.used_weight,
+            };
+            if used.any_gt(threshold * weight_limit) {
+                Self::deposit_event(Event::<T>::WeightNearExhaustion {
+                    trace_id,
+                    vm_id,
+                    used,
+                    limit: weight_limit,
+                });
+            }
+        }
+    }
+
+    // `storage_deposit_limit` is only used by WASM calls, and the access list, gas limit and
+    // EVM source overrides by EVM calls.
+    #[cfg_attr(
+        any(not(feature = "evm-vm"), not(feature = "wasm-vm")),
+        allow(unused_variables)
+    )]
+    fn execute_call(
+        mut context: Context,
         vm_id: VmId,
         source: T::AccountId,
         target: Vec<u8>,
         input: Vec<u8>,
         value: Balance,
         storage_deposit_limit: Option<Balance>,
+        maybe_access_list: Option<Vec<(H160, Vec<H256>)>>,
+        gas_limit_override: Option<u64>,
+        evm_source_override: Option<H160>,
         skip_execution: bool,
+        native_source: bool,
     ) -> CallResult {
-        let overheads = match vm_id {
-            VmId::Evm => WeightInfoOf::<T>::evm_call_overheads(),
-            VmId::Wasm => WeightInfoOf::<T>::wasm_call_overheads(),
-        };
+        // Nested calls inherit the trace id of the outermost call.
+        if Self::is_in_xvm() {
+            context.trace_id = TRACE_ID.with(|trace_id| *trace_id.borrow());
+        }
+        log::trace!(
+            target: "xvm::do_call",
+            "XVM call {:?}: {:?} {:?}, {:?}",
+            context.trace_id, vm_id, source, target,
+        );
+
+        Self::record_vm_path(vm_id);
+
+        let overheads = Self::call_overheads(vm_id);
 
         ensure!(
-            context.source_vm_id != vm_id,
-            CallFailure::error(SameVmCallDenied, overheads)
+            !XvmPaused::<T>::get(),
+            CallFailure::error(Paused, overheads)
         );
 
-        // Set `IN_XVM` to true & check reentrance.
-        if IN_XVM.with(|in_xvm| in_xvm.replace(true)) {
-            return Err(CallFailure::error(ReentranceDenied, overheads));
+        // Only calls executed in a VM are counted, by `count_call`, so denied calls have no
+        // state changes.
+        ensure!(
+            CallsInBlock::<T>::get() < T::MaxCallsPerBlock::get(),
+            CallFailure::error(CallLimitReached, overheads)
+        );
+
+        let denial_overheads = WeightInfoOf::<T>::call_denied_overheads();
+
+        ensure!(
+            native_source || context.source_vm_id != vm_id,
+            CallFailure::denied(SameVmCallDenied, overheads, denial_overheads)
+        );
+
+        // Set `IN_XVM` to true & check reentrance. The only storage reads so far are `XvmPaused`
+        // and `CallsInBlock`, both covered by `denial_overheads`.
+        let in_xvm_guard = InXvmGuard::enter()
+            .ok_or_else(|| CallFailure::denied(ReentranceDenied, overheads, denial_overheads))?;
+
+        let target = XvmTarget::<T::AccountId>::try_from((vm_id, target))
+            .map_err(|revert| CallFailure::revert(revert, overheads))?;
+
+        // Reads of the checks below, charged on top of `denial_overheads` if the call is denied.
+        let mut checks_weight = denial_overheads;
+
+        if let Some(max_calls) = T::MaxCallsPerTarget::convert(target.clone()) {
+            checks_weight.saturating_accrue(T::DbWeight::get().reads(1));
+            ensure!(
+                Self::calls_to_target_in_block(&target) < max_calls,
+                CallFailure::error(TargetRateLimited, overheads)
+            );
         }
 
-        let res = match vm_id {
-            VmId::Evm => Pallet::<T>::evm_call(
-                context,
-                source,
-                target,
-                input,
-                value,
-                overheads,
-                skip_execution,
-            ),
-            VmId::Wasm => Pallet::<T>::wasm_call(
-                context,
-                source,
-                target,
-                input,
-                value,
-                overheads,
-                storage_deposit_limit,
-                skip_execution,
-            ),
+        let value = Self::ensure_transfer_allowed(
+            &source,
+            evm_source_override,
+            &target,
+            value,
+            overheads,
+            &mut checks_weight,
+        )?;
+
+        TRACE_ID.with(|trace_id| *trace_id.borrow_mut() = context.trace_id);
+        let (trace_id, weight_limit) = (context.trace_id, context.weight_limit);
+
+        // The transactional boundary of the value transfer is the VM call: both VMs transfer
+        // `value` as part of the call, and roll back their state changes, the transfer
+        // included, if the call reverts or fails. Only the call counters and EVM nonces are
+        // kept. On top of that, oversized output rolls back a successful call, the counters
+        // are increased after the rollback to keep them.
+        let mut vm_reached = false;
+        let counted_target = target.clone();
+        let res = Self::revertible(OutputTooLarge, || {
+            #[cfg(any(test, feature = "noop-vm"))]
+            if NOOP_VM.with(|noop_vm| *noop_vm.borrow()) {
+                return Self::bound_output(vm_id, Self::noop_call(context, input, overheads));
+            }
+            let vm_result = match target {
+                #[cfg(feature = "evm-vm")]
+                XvmTarget::Evm(target) => Pallet::<T>::evm_call(
+                    context,
+                    evm_source_override.unwrap_or_else(|| Self::mapped_evm_address(source)),
+                    target,
+                    input,
+                    value,
+                    overheads,
+                    maybe_access_list,
+                    gas_limit_override,
+                    skip_execution,
+                ),
+                #[cfg(not(feature = "evm-vm"))]
+                XvmTarget::Evm(_) => Err(CallFailure::error(VmNotSupported(VmId::Evm), overheads)),
+                #[cfg(feature = "wasm-vm")]
+                XvmTarget::Wasm(dest) => Pallet::<T>::wasm_call(
+                    context,
+                    source,
+                    dest,
+                    input,
+                    value,
+                    overheads,
+                    storage_deposit_limit,
+                    skip_execution,
+                ),
+                #[cfg(not(feature = "wasm-vm"))]
+                XvmTarget::Wasm(_) => {
+                    Err(CallFailure::error(VmNotSupported(VmId::Wasm), overheads))
+                }
+            }?;
+            vm_reached = !skip_execution;
+            vm_result.and_then(|output| Self::bound_output(vm_id, output))
+        });
+        let res = if vm_reached {
+            Self::count_call(counted_target, res)
+        } else {
+            res
         };
 
-        // Set `IN_XVM` to false.
-        // We should make sure that this line is executed whatever the execution path.
-        let _ = IN_XVM.with(|in_xvm| in_xvm.take());
+        drop(in_xvm_guard);
+        debug_assert!(
+            !Self::is_in_xvm(),
+            "`IN_XVM` must be restored after the call"
+        );
+
+        if !skip_execution {
+            Self::check_weight_exhaustion(trace_id, vm_id, &res, weight_limit);
+        }
 
         res
     }
 
+    /// Check the value transfer of a call from `source` to `target`, before entering the VM:
+    /// self calls if denied, the existential deposit of the target, the max transfer value and
+    /// the balance of the source.
+    ///
+    /// For EVM targets, the value is debited from `evm_source`, or from the mapped EVM address
+    /// of `source` if `None`. Returns the value to transfer, topped up to the existential
+    /// deposit of the target with the `TopUp` policy. The reads of the checks denying the call
+    /// are accrued to `checks_weight`.
+    fn ensure_transfer_allowed(
+        source: &T::AccountId,
+        evm_source: Option<H160>,
+        target: &XvmTarget<T::AccountId>,
+        mut value: Balance,
+        overheads: Weight,
+        checks_weight: &mut Weight,
+    ) -> Result<Balance, CallFailure> {
+        if T::DenySelfCalls::get() {
+            let is_self_call = match target {
+                XvmTarget::Evm(target) => {
+                    let source = evm_source.unwrap_or_else(|| {
+                        checks_weight.saturating_accrue(T::DbWeight::get().reads(1));
+                        Self::mapped_evm_address(source.clone())
+                    });
+                    *target == source
+                }
+                XvmTarget::Wasm(target) => target == source,
+            };
+            ensure!(
+                !is_self_call,
+                CallFailure::denied(SelfCallDenied, overheads, *checks_weight)
+            );
+        }
+
+        // Check the target keeps the existential deposit, the topped up value is checked by
+        // the value checks below.
+        let existential_deposit_policy = T::TargetExistentialDepositPolicy::get();
+        if value > 0 && existential_deposit_policy != ExistentialDepositPolicy::Allow {
+            let target_balance: Option<Balance> = match target {
+                #[cfg(feature = "evm-vm")]
+                XvmTarget::Evm(target) => {
+                    Some(T::EthereumTransact::account_balance(*target).unique_saturated_into())
+                }
+                #[cfg(not(feature = "evm-vm"))]
+                XvmTarget::Evm(_) => None,
+                XvmTarget::Wasm(target) => Some(<T as Config>::Currency::free_balance(target)),
+            };
+            let minimum_balance = <T as Config>::Currency::minimum_balance();
+            if let Some(target_balance) = target_balance {
+                if target_balance.saturating_add(value) < minimum_balance {
+                    match existential_deposit_policy {
+                        ExistentialDepositPolicy::Allow => (),
+                        ExistentialDepositPolicy::Reject => {
+                            return Err(CallFailure::error(BelowExistentialDeposit, overheads))
+                        }
+                        ExistentialDepositPolicy::TopUp => value = minimum_balance - target_balance,
+                    }
+                }
+            }
+        }
+
+        if let Some(max_transfer_value) = T::MaxTransferValue::get() {
+            ensure!(
+                value <= max_transfer_value,
+                CallFailure::error(ValueTooLarge, overheads)
+            );
+        }
+
+        // Check the source can pay the value, on the account the target VM debits.
+        if value > 0 {
+            match target {
+                #[cfg(feature = "evm-vm")]
+                XvmTarget::Evm(_) => {
+                    let evm_source =
+                        evm_source.unwrap_or_else(|| Self::mapped_evm_address(source.clone()));
+                    let source_balance: Balance =
+                        T::EthereumTransact::account_balance(evm_source).unique_saturated_into();
+                    ensure!(
+                        source_balance >= value,
+                        CallFailure::error(InsufficientBalance, overheads)
+                    );
+                }
+                #[cfg(not(feature = "evm-vm"))]
+                XvmTarget::Evm(_) => (),
+                // `pallet_contracts` keeps the source alive on transfer.
+                XvmTarget::Wasm(_) => {
+                    let free_balance = <T as Config>::Currency::free_balance(source);
+                    ensure!(
+                        free_balance >= value,
+                        CallFailure::error(InsufficientBalance, overheads)
+                    );
+                    ensure!(
+                        free_balance - value >= <T as Config>::Currency::minimum_balance(),
+                        CallFailure::error(WouldReapAccount, overheads)
+                    );
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// Bound the output size to the max output size of `vm_id`.
+    ///
+    /// Oversized output is truncated if `T::TruncateOversizedOutput` is `true`, otherwise
+    /// the call fails with `OutputTooLarge`.
+    fn bound_output(vm_id: VmId, mut output: CallOutput) -> CallResult {
+        let max_output_size = match vm_id {
+            VmId::Evm => T::MaxEvmOutputSize::get(),
+            VmId::Wasm => T::MaxWasmOutputSize::get(),
+        } as usize;
+        if output.output.len() <= max_output_size {
+            return Ok(output);
+        }
+
+        ensure!(
+            T::TruncateOversizedOutput::get(),
+            CallFailure::revert(OutputTooLarge, output.used_weight)
+        );
+        output.output.truncate(max_output_size);
+        output.truncated = true;
+        Ok(output)
+    }
+
+    /// Execute `call` in a storage transaction, which is rolled back if `call` fails with
+    /// `revert`.
+    fn revertible(revert: FailureRevert, call: impl FnOnce() -> CallResult) -> CallResult {
+        let result = with_transaction(|| {
+            let result = call();
+            let rollback = matches!(
+                &result,
+                Err(CallFailure {
+                    reason: FailureReason::Revert(reason),
+                    ..
+                }) if *reason == revert
+            );
+            if rollback {
+                TransactionOutcome::Rollback(Ok::<_, DispatchError>(result))
+            } else {
+                TransactionOutcome::Commit(Ok(result))
+            }
+        });
+        // Only fails if the transactional layer limit is exceeded.
+        result.unwrap_or_else(|e| {
+            let reason: &str = e.into();
+            Err(CallFailure::error(VmError(reason.into()), Weight::zero()))
+        })
+    }
+
+    /// Call the EVM, or fail with the outer error if the call is rejected before reaching
+    /// the VM.
+    #[cfg(feature = "evm-vm")]
     fn evm_call(
         context: Context,
-        source: T::AccountId,
-        target: Vec<u8>,
+        source: H160,
+        target: H160,
         input: Vec<u8>,
         value: Balance,
         overheads: Weight,
+        maybe_access_list: Option<Vec<(H160, Vec<H256>)>>,
+        gas_limit_override: Option<u64>,
         skip_execution: bool,
-    ) -> CallResult {
-        log::trace!(
-            target: "xvm::evm_call",
-            "Calling EVM: {:?} {:?}, {:?}, {:?}, {:?}",
-            context, source, target, input, value,
-        );
+    ) -> Result<CallResult, CallFailure> {
+        // Inputs and outputs may be large, skip formatting them if not tracing.
+        if log::log_enabled!(target: "xvm::evm_call", log::Level::Trace) {
+            log::trace!(
+                target: "xvm::evm_call",
+                "Calling EVM: {:?} {:?}, {:?}, {:?}, {:?}, {:?}, {:?}",
+                context, source, target, input, value, maybe_access_list, gas_limit_override,
+            );
+        }
 
         ensure!(
-            target.len() == H160::len_bytes(),
-            CallFailure::revert(InvalidTarget, overheads)
+            T::AllowedEvmTargets::contains(&target),
+            CallFailure::revert(TargetNotAllowed, overheads)
         );
-        let target_decoded = Decode::decode(&mut target.as_ref())
-            .map_err(|_| CallFailure::revert(InvalidTarget, overheads))?;
         let bounded_input = EthereumTxInput::try_from(input)
             .map_err(|_| CallFailure::revert(InputTooLarge, overheads))?;
+        Self::ensure_access_list_bounded(&maybe_access_list, overheads)?;
+
+        ensure!(
+            gas_limit_override.is_some() || !T::RequireExplicitGasLimit::get(),
+            CallFailure::error(GasLimitRequired, overheads)
+        );
 
-        let value_u256 = U256::from(value);
+        let value_u256 = balance_to_u256(value);
         // With overheads, less weight is available.
         let weight_limit = context.weight_limit.saturating_sub(overheads);
-        let gas_limit = U256::from(T::GasWeightMapping::weight_to_gas(weight_limit));
+        let weight_gas_limit = T::GasWeightMapping::weight_to_gas(weight_limit);
+        // The explicit gas limit is still bounded by the weight limit.
+        let gas_limit = U256::from(gas_limit_override.map_or(weight_gas_limit, |gas_limit| {
+            gas_limit.min(weight_gas_limit)
+        }));
 
-        let source = T::AccountMapping::into_h160(source);
         let tx = CheckedEthereumTx {
             gas_limit,
-            target: target_decoded,
+            target,
             value: value_u256,
             input: bounded_input,
-            maybe_access_list: None,
+            maybe_access_list,
         };
 
         // Note the skip execution check should be exactly before `T::EthereumTransact::xvm_transact`
         // to benchmark the correct overheads.
         if skip_execution {
-            return Ok(CallOutput::new(vec![], overheads));
+            return Ok(Ok(CallOutput::new(vec![], overheads)));
+        }
+
+        Ok(Self::evm_transact(
+            context,
+            source,
+            tx,
+            overheads,
+            weight_limit,
+        ))
+    }
+
+    /// Fails with `AccessListTooLarge` if the access list has more than
+    /// `T::MaxAccessListEntries` entries, counting the addresses and the storage keys.
+    #[cfg(feature = "evm-vm")]
+    fn ensure_access_list_bounded(
+        maybe_access_list: &Option<Vec<(H160, Vec<H256>)>>,
+        overheads: Weight,
+    ) -> Result<(), CallFailure> {
+        if let Some(access_list) = maybe_access_list {
+            let entries = access_list
+                .iter()
+                .fold(0usize, |acc, (_, keys)| acc.saturating_add(1 + keys.len()));
+            ensure!(
+                entries <= T::MaxAccessListEntries::get() as usize,
+                CallFailure::revert(AccessListTooLarge, overheads)
+            );
         }
+        Ok(())
+    }
 
+    /// Execute the EVM transaction `tx`, with `weight_limit` as the weight limit excluding
+    /// the overheads.
+    #[cfg(feature = "evm-vm")]
+    fn evm_transact(
+        context: Context,
+        source: H160,
+        tx: CheckedEthereumTx,
+        overheads: Weight,
+        weight_limit: Weight,
+    ) -> CallResult {
+        let (target, value_u256) = (tx.target, tx.value);
         let transact_result = T::EthereumTransact::xvm_transact(source, tx);
-        log::trace!(
-            target: "xvm::evm_call",
-            "EVM call result: {:?}", transact_result,
-        );
+        if log::log_enabled!(target: "xvm::evm_call", log::Level::Trace) {
+            log::trace!(
+                target: "xvm::evm_call",
+                "EVM call result: {:?}", transact_result,
+            );
+        }
 
         match transact_result {
             Ok((post_dispatch_info, call_info)) => {
+                // The actual weight is of the gas used after refunds, which EIP-3529 caps at
+                // 1/5 of the gas used. It's charged as is: refunds only depend on the state, so
+                // the weight stays deterministic, and the cap bounds how much refunds can
+                // discount the execution.
                 let used_weight = post_dispatch_info
                     .actual_weight
                     .unwrap_or_default()
                     .saturating_add(overheads);
+                let maybe_discard = |output: Vec<u8>| {
+                    if context.discard_output {
+                        vec![]
+                    } else {
+                        output
+                    }
+                };
                 match call_info.exit_reason {
-                    ExitReason::Succeed(_) => Ok(CallOutput::new(call_info.value, used_weight)),
+                    // The used gas is reported as is, not converted back from the weight.
+                    ExitReason::Succeed(reason) if value_u256.is_zero() => {
+                        Ok(CallOutput::new(maybe_discard(call_info.value), used_weight)
+                            .with_status(evm_call_status(reason))
+                            .with_used_gas(call_info.used_gas.standard))
+                    }
+                    ExitReason::Succeed(reason) => {
+                        let target_balance = T::EthereumTransact::account_balance(target);
+                        let mut output = CallOutput::new(
+                            maybe_discard(call_info.value),
+                            used_weight.saturating_add(T::DbWeight::get().reads(1)),
+                        )
+                        .with_status(evm_call_status(reason))
+                        .with_used_gas(call_info.used_gas.standard);
+                        output.target_balance_after = Some(target_balance);
+                        Ok(output)
+                    }
                     ExitReason::Revert(_) => {
                         // On revert, the `call_info.value` is the encoded error data. Refer to Contract
                         // ABI specification for details. https://docs.soliditylang.org/en/latest/abi-spec.html#errors
                         Err(CallFailure::revert(VmRevert(call_info.value), used_weight))
                     }
+                    ExitReason::Error(ExitError::OutOfGas) => {
+                        Err(CallFailure::error(OutOfGas, used_weight))
+                    }
                     ExitReason::Error(err) => Err(CallFailure::error(
                         VmError(format!("EVM call error: {:?}", err).into()),
                         used_weight,
@@ -254,14 +2094,17 @@ where
                     ExitReason::Fatal(err) => Err(CallFailure::error(
                         VmError(format!("EVM call error: {:?}", err).into()),
                         used_weight,
-                    )),
+                    )
+                    .with_category(ExitCategory::Fatal)),
                 }
             }
             Err(e) => {
+                // If the actual weight is unknown, the EVM may have done any amount of work
+                // before failing, so the full weight limit is charged to avoid underpricing.
                 let used_weight = e
                     .post_info
                     .actual_weight
-                    .unwrap_or_default()
+                    .unwrap_or(weight_limit)
                     .saturating_add(overheads);
                 Err(CallFailure::error(
                     VmError(format!("EVM call error: {:?}", e.error).into()),
@@ -271,26 +2114,43 @@ where
         }
     }
 
+    /// Call the WASM, or fail with the outer error if the call is rejected before reaching
+    /// the VM.
+    #[cfg(feature = "wasm-vm")]
     fn wasm_call(
         context: Context,
         source: T::AccountId,
-        target: Vec<u8>,
+        dest: T::AccountId,
         input: Vec<u8>,
         value: Balance,
         overheads: Weight,
         storage_deposit_limit: Option<Balance>,
         skip_execution: bool,
-    ) -> CallResult {
-        log::trace!(
-            target: "xvm::wasm_call",
-            "Calling WASM: {:?} {:?}, {:?}, {:?}, {:?}, {:?}",
-            context, source, target, input, value, storage_deposit_limit,
-        );
+    ) -> Result<CallResult, CallFailure> {
+        // Inputs and outputs may be large, skip formatting them if not tracing.
+        if log::log_enabled!(target: "xvm::wasm_call", log::Level::Trace) {
+            log::trace!(
+                target: "xvm::wasm_call",
+                "Calling WASM: {:?} {:?}, {:?}, {:?}, {:?}, {:?}",
+                context, source, dest, input, value, storage_deposit_limit,
+            );
+        }
 
-        let dest = {
-            let error = CallFailure::revert(InvalidTarget, overheads);
-            Decode::decode(&mut target.as_ref()).map_err(|_| error.clone())
-        }?;
+        // Keep `storage_deposit_limit` out of `value` for the storage deposit, the call fails
+        // if there's no limit or `value` can't cover it.
+        let value = if context.storage_deposit_from_value {
+            storage_deposit_limit
+                .and_then(|limit| value.checked_sub(limit))
+                .ok_or_else(|| CallFailure::error(OutOfStorageDeposit, overheads))?
+        } else {
+            value
+        };
+
+        let input = if context.source_vm_id == VmId::Evm {
+            Self::translate_selector(input)
+        } else {
+            input
+        };
 
         // With overheads, less weight is available.
         let weight_limit = context.weight_limit.saturating_sub(overheads);
@@ -298,35 +2158,213 @@ where
         // Note the skip execution check should be exactly before `pallet_contracts::bare_call`
         // to benchmark the correct overheads.
         if skip_execution {
-            return Ok(CallOutput::new(vec![], overheads));
+            return Ok(Ok(CallOutput::new(vec![], overheads)));
         }
 
+        // `pallet_contracts` charges the storage deposit to the call origin, which is `source`
+        // here. For calls from the XVM precompile or chain extension, `source` is the calling
+        // contract itself, so intermediate contracts can't pass the deposit on to others.
         let call_result = pallet_contracts::Pallet::<T>::bare_call(
             source,
-            dest,
-            value,
+            dest.clone(),
+            value.unique_saturated_into(),
             weight_limit,
-            storage_deposit_limit,
+            storage_deposit_limit.map(|limit| limit.unique_saturated_into()),
             input,
             DebugInfo::Skip,
             CollectEvents::Skip,
-            Determinism::Enforced,
+            Self::wasm_determinism(),
         );
-        log::trace!(target: "xvm::wasm_call", "WASM call result: {:?}", call_result);
+        if log::log_enabled!(target: "xvm::wasm_call", log::Level::Trace) {
+            log::trace!(target: "xvm::wasm_call", "WASM call result: {:?}", call_result);
+        }
 
         let used_weight = call_result.gas_consumed.saturating_add(overheads);
-        match call_result.result {
+        let storage_deposit: Balance = call_result
+            .storage_deposit
+            .charge_or_zero()
+            .unique_saturated_into();
+        let result = match call_result.result {
             Ok(val) => {
                 if val.flags.contains(ReturnFlags::REVERT) {
+                    // Revert data is kept as is, neither bounded nor discarded, for callers
+                    // to decode structured errors with `decode_wasm_revert_error`.
                     Err(CallFailure::revert(VmRevert(val.data), used_weight))
                 } else {
-                    Ok(CallOutput::new(val.data, used_weight))
+                    let output = if context.discard_output {
+                        vec![]
+                    } else {
+                        val.data
+                    };
+                    Ok(CallOutput::new(output, used_weight)
+                        .with_status(val.flags.bits())
+                        .with_storage_deposit(storage_deposit))
                 }
             }
-            Err(error) => Err(CallFailure::error(
-                VmError(format!("WASM call error: {:?}", error).into()),
-                used_weight,
-            )),
+            Err(error) => {
+                let out_of_storage_deposit = [
+                    pallet_contracts::Error::<T>::StorageDepositLimitExhausted.into(),
+                    pallet_contracts::Error::<T>::StorageDepositNotEnoughFunds.into(),
+                ]
+                .contains(&error);
+                if out_of_storage_deposit {
+                    Err(CallFailure::error(OutOfStorageDeposit, used_weight))
+                } else if error == DispatchError::from(pallet_contracts::Error::<T>::OutOfGas) {
+                    Err(CallFailure::error(OutOfGas, used_weight))
+                } else if error
+                    == DispatchError::from(pallet_contracts::Error::<T>::ContractTrapped)
+                {
+                    Err(CallFailure::error(ContractTrapped, used_weight))
+                } else if error
+                    == DispatchError::from(pallet_contracts::Error::<T>::ContractNotFound)
+                {
+                    Err(CallFailure::error(ContractNotFound, used_weight))
+                } else {
+                    Err(CallFailure::error(
+                        VmError(format!("WASM call error: {:?}", error).into()),
+                        used_weight,
+                    ))
+                }
+            }
+        };
+        Ok(result)
+    }
+
+    /// Echo `input` as output, using the overheads only, in place of the target VM if
+    /// `NOOP_VM` is set.
+    ///
+    /// Only in the pallet tests or with the `noop-vm` feature, to test the XVM dispatch without
+    /// the EVM and WASM backends. The value isn't transferred, and no counters are touched.
+    #[cfg(any(test, feature = "noop-vm"))]
+    fn noop_call(context: Context, input: Vec<u8>, overheads: Weight) -> CallOutput {
+        let output = if context.discard_output {
+            vec![]
+        } else {
+            input
+        };
+        CallOutput::new(output, overheads)
+    }
+
+    /// Increase the call counters and weight statistics of the VM of `target`, the calls in
+    /// block, the calls to `target` if limited, and the failed calls counter, and charge
+    /// their weight.
+    ///
+    /// Only called after the VM execution, so the overheads benchmarks with `skip_execution`
+    /// don't touch the counters, and outside the `OutputTooLarge` rollback, so the counters
+    /// are kept if the output is too large.
+    fn count_call(target: XvmTarget<T::AccountId>, mut result: CallResult) -> CallResult {
+        let vm_id = match target {
+            XvmTarget::Evm(_) => {
+                EvmCallCount::<T>::mutate(|count| *count = count.saturating_add(1));
+                VmId::Evm
+            }
+            XvmTarget::Wasm(_) => {
+                WasmCallCount::<T>::mutate(|count| *count = count.saturating_add(1));
+                VmId::Wasm
+            }
+        };
+        let used_weight = match &result {
+            Ok(output) => output.used_weight,
+            Err(failure) => failure.used_weight,
+        };
+        TotalCallWeight::<T>::mutate(vm_id, |total| total.saturating_accrue(used_weight));
+        MaxWeightObserved::<T>::mutate(vm_id, |max| *max = max.max(used_weight));
+        CallsInBlock::<T>::mutate(|calls| *calls = calls.saturating_add(1));
+        let target_counter_weight = if T::MaxCallsPerTarget::convert(target.clone()).is_some() {
+            let now = frame_system::Pallet::<T>::block_number();
+            CallsToTargetInBlock::<T>::mutate(&target, |(block, calls)| {
+                if *block != now {
+                    *block = now;
+                    *calls = 0;
+                }
+                *calls = calls.saturating_add(1);
+            });
+            T::DbWeight::get().reads_writes(1, 1)
+        } else {
+            Weight::zero()
+        };
+
+        match result {
+            Ok(ref mut output) => output.used_weight.saturating_accrue(
+                T::DbWeight::get()
+                    .reads_writes(4, 4)
+                    .saturating_add(target_counter_weight),
+            ),
+            Err(ref mut failure) => {
+                FailedCallCount::<T>::mutate(|count| *count = count.saturating_add(1));
+                failure.used_weight.saturating_accrue(
+                    T::DbWeight::get()
+                        .reads_writes(5, 5)
+                        .saturating_add(target_counter_weight),
+                );
+            }
+        }
+        result
+    }
+
+    /// Number of XVM calls executed to `target` in the current block.
+    pub fn calls_to_target_in_block(target: &XvmTarget<T::AccountId>) -> u32 {
+        let (block, calls) = CallsToTargetInBlock::<T>::get(target);
+        if block == frame_system::Pallet::<T>::block_number() {
+            calls
+        } else {
+            0
+        }
+    }
+
+    /// The account of the pallet, derived from `T::PalletId`.
+    pub fn account_id() -> T::AccountId {
+        T::PalletId::get().into_account_truncating()
+    }
+
+    /// Human readable diagnostic of the weight used by a call in `vm_id` against its weight
+    /// limit, with the equivalent gas for EVM calls, to debug `OutOfGas` failures.
+    #[cfg_attr(not(feature = "evm-vm"), allow(unused_variables))]
+    pub fn weight_diagnostic(vm_id: VmId, used_weight: Weight, weight_limit: Weight) -> Vec<u8> {
+        let diagnostic = format!(
+            "used {}/{} ref time, {}/{} proof size",
+            used_weight.ref_time(),
+            weight_limit.ref_time(),
+            used_weight.proof_size(),
+            weight_limit.proof_size(),
+        );
+        #[cfg(feature = "evm-vm")]
+        let diagnostic = if vm_id == VmId::Evm {
+            format!(
+                "{}, {}/{} gas",
+                diagnostic,
+                T::GasWeightMapping::weight_to_gas(used_weight),
+                T::GasWeightMapping::weight_to_gas(weight_limit),
+            )
+        } else {
+            diagnostic
+        };
+        diagnostic.into_bytes()
+    }
+
+    /// The VMs XVM calls can target in this runtime, calls into other VMs fail with
+    /// `VmNotSupported` or `InvalidVmId`.
+    pub fn supported_vms() -> Vec<VmId> {
+        let mut vms = Vec::new();
+        if cfg!(feature = "evm-vm") {
+            vms.push(VmId::Evm);
+        }
+        if cfg!(feature = "wasm-vm") {
+            vms.push(VmId::Wasm);
+        }
+        vms
+    }
+
+    /// Weight statistics of the calls executed in `vm_id`, to compute the average weight.
+    pub fn call_weight_stats(vm_id: VmId) -> CallWeightStats {
+        let calls = match vm_id {
+            VmId::Evm => EvmCallCount::<T>::get(),
+            VmId::Wasm => WasmCallCount::<T>::get(),
+        };
+        CallWeightStats {
+            calls,
+            total_weight: TotalCallWeight::<T>::get(vm_id),
+            max_weight: MaxWeightObserved::<T>::get(vm_id),
         }
     }
 
@@ -348,7 +2386,11 @@ where
             input,
             value,
             storage_deposit_limit,
+            None,
+            None,
+            None,
             true,
+            false,
         )
     }
 }
@@ -21,6 +21,7 @@
 use super::*;
 use crate as pallet_xvm;
 
+use astar_primitives::xvm::EvmAbiType;
 use fp_evm::{CallInfo as EvmCallInfo, ExitReason, ExitSucceed, UsedGas};
 use frame_support::{
     construct_runtime,
@@ -28,15 +29,17 @@ use frame_support::{
     pallet_prelude::*,
     parameter_types,
     sp_io::TestExternalities,
-    traits::{ConstBool, ConstU128, ConstU64, Nothing},
+    traits::{ConstBool, ConstU128, ConstU64, GenesisBuild, Nothing},
+    PalletId,
 };
-use sp_core::{H160, H256};
+use frame_system::EnsureRoot;
+use sp_core::{H160, H256, U256};
 use sp_runtime::{
     testing::Header,
-    traits::{AccountIdLookup, BlakeTwo256},
-    AccountId32,
+    traits::{AccountIdLookup, BlakeTwo256, Convert},
+    AccountId32, Perbill,
 };
-use sp_std::cell::RefCell;
+use sp_std::{cell::RefCell, collections::btree_map::BTreeMap};
 
 parameter_types! {
     pub BlockWeights: frame_system::limits::BlockWeights =
@@ -132,8 +135,27 @@ impl astar_primitives::ethereum_checked::AccountMapping<AccountId> for HashedAcc
     }
 }
 
+/// Only `BOB` has an entry in the primary mapping.
+pub struct PrimaryAccountMapping;
+impl astar_primitives::ethereum_checked::TryAccountMapping<AccountId> for PrimaryAccountMapping {
+    fn try_into_h160(account_id: &AccountId) -> Option<H160> {
+        (*account_id == BOB).then(|| BOB_EVM_ADDRESS)
+    }
+}
+
+pub(crate) const BOB_EVM_ADDRESS: H160 = H160::repeat_byte(0xB0);
+
 thread_local! {
     static TRANSACTED: RefCell<Option<(H160, CheckedEthereumTx)>> = RefCell::new(None);
+    static NONCE: RefCell<U256> = RefCell::new(U256::zero());
+    static TRANSACT_ERROR: RefCell<Option<DispatchErrorWithPostInfo>> = RefCell::new(None);
+    static EVM_BALANCES: RefCell<BTreeMap<H160, U256>> = RefCell::new(BTreeMap::new());
+    static IN_XVM_ON_TRANSACT: RefCell<Option<bool>> = RefCell::new(None);
+    static OUTPUT: RefCell<Vec<u8>> = RefCell::new(Vec::new());
+    static EXIT_REASON: RefCell<ExitReason> = RefCell::new(ExitReason::Succeed(ExitSucceed::Returned));
+    static USED_GAS: RefCell<U256> = RefCell::new(U256::zero());
+    static DEFER_ON_TRANSACT: RefCell<Option<DeferredXvmCall<AccountId, BlockNumber>>> = RefCell::new(None);
+    static PANIC_ON_TRANSACT: RefCell<bool> = RefCell::new(false);
 }
 
 pub struct MockEthereumTransact;
@@ -142,30 +164,98 @@ impl MockEthereumTransact {
         let transacted = TRANSACTED.with(|v| v.borrow().clone());
         assert_eq!(transacted, Some((source, checked_tx)));
     }
+
+    pub(crate) fn in_xvm_on_transact() -> Option<bool> {
+        IN_XVM_ON_TRANSACT.with(|v| *v.borrow())
+    }
+
+    pub(crate) fn set_balance(address: H160, balance: Balance) {
+        EVM_BALANCES.with(|v| v.borrow_mut().insert(address, U256::from(balance)));
+    }
+
+    pub(crate) fn set_output(output: Vec<u8>) {
+        OUTPUT.with(|v| *v.borrow_mut() = output);
+    }
+
+    pub(crate) fn set_exit_reason(exit_reason: ExitReason) {
+        EXIT_REASON.with(|v| *v.borrow_mut() = exit_reason);
+    }
+
+    pub(crate) fn set_used_gas(used_gas: U256) {
+        USED_GAS.with(|v| *v.borrow_mut() = used_gas);
+    }
+
+    pub(crate) fn set_error(error: Option<DispatchErrorWithPostInfo>) {
+        TRANSACT_ERROR.with(|v| *v.borrow_mut() = error);
+    }
+
+    /// Defer `call` on every transact, like a contract scheduling a deferred call.
+    pub(crate) fn set_defer_on_transact(call: Option<DeferredXvmCall<AccountId, BlockNumber>>) {
+        DEFER_ON_TRANSACT.with(|v| *v.borrow_mut() = call);
+    }
+
+    pub(crate) fn set_panic_on_transact(panic: bool) {
+        PANIC_ON_TRANSACT.with(|v| *v.borrow_mut() = panic);
+    }
+
+    pub(crate) fn set_nonce(nonce: U256) {
+        NONCE.with(|v| *v.borrow_mut() = nonce);
+    }
+}
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper for MockEthereumTransact {
+    fn set_evm_balance(address: H160, balance: Balance) {
+        Self::set_balance(address, balance);
+    }
 }
+
 impl CheckedEthereumTransact for MockEthereumTransact {
     fn xvm_transact(
         source: H160,
         checked_tx: CheckedEthereumTx,
     ) -> Result<(PostDispatchInfo, EvmCallInfo), DispatchErrorWithPostInfo> {
+        let (target, value) = (checked_tx.target, checked_tx.value);
+        IN_XVM_ON_TRANSACT.with(|v| *v.borrow_mut() = Some(Xvm::is_in_xvm()));
+        if PANIC_ON_TRANSACT.with(|v| *v.borrow()) {
+            panic!("transact panicked");
+        }
         TRANSACTED.with(|v| *v.borrow_mut() = Some((source, checked_tx)));
+        if let Some(call) = DEFER_ON_TRANSACT.with(|v| v.borrow().clone()) {
+            let _ = Xvm::defer_call(call);
+        }
+        if let Some(error) = TRANSACT_ERROR.with(|v| v.borrow().clone()) {
+            return Err(error);
+        }
+        EVM_BALANCES.with(|v| {
+            let mut balances = v.borrow_mut();
+            let balance = balances.entry(target).or_default();
+            *balance = balance.saturating_add(value);
+        });
         Ok((
             PostDispatchInfo {
                 actual_weight: Default::default(),
                 pays_fee: Default::default(),
             },
             EvmCallInfo {
-                exit_reason: ExitReason::Succeed(ExitSucceed::Returned),
-                value: Default::default(),
+                exit_reason: EXIT_REASON.with(|v| v.borrow().clone()),
+                value: OUTPUT.with(|v| v.borrow().clone()),
                 used_gas: UsedGas {
-                    standard: Default::default(),
-                    effective: Default::default(),
+                    standard: USED_GAS.with(|v| *v.borrow()),
+                    effective: USED_GAS.with(|v| *v.borrow()),
                 },
                 logs: Default::default(),
                 weight_info: None,
             },
         ))
     }
+
+    fn account_nonce(_source: H160) -> U256 {
+        NONCE.with(|v| *v.borrow())
+    }
+
+    fn account_balance(address: H160) -> U256 {
+        EVM_BALANCES.with(|v| v.borrow().get(&address).copied().unwrap_or_default())
+    }
 }
 
 pub struct MockGasWeightMapping;
@@ -178,11 +268,94 @@ impl GasWeightMapping for MockGasWeightMapping {
     }
 }
 
+/// EVM target XVM calls aren't allowed into.
+pub(crate) const BLOCKED_EVM_TARGET: H160 = H160::repeat_byte(0xBB);
+
+pub struct AllowedEvmTargets;
+impl Contains<H160> for AllowedEvmTargets {
+    fn contains(target: &H160) -> bool {
+        *target != BLOCKED_EVM_TARGET
+    }
+}
+
+/// EVM target with a limited number of XVM calls per block.
+pub(crate) const RATE_LIMITED_EVM_TARGET: H160 = H160::repeat_byte(0xCC);
+pub(crate) const RATE_LIMITED_EVM_TARGET_MAX_CALLS: u32 = 2;
+
+/// Only `RATE_LIMITED_EVM_TARGET` is rate limited.
+pub struct MockMaxCallsPerTarget;
+impl Convert<XvmTarget<AccountId>, Option<u32>> for MockMaxCallsPerTarget {
+    fn convert(target: XvmTarget<AccountId>) -> Option<u32> {
+        match target {
+            XvmTarget::Evm(target) if target == RATE_LIMITED_EVM_TARGET => {
+                Some(RATE_LIMITED_EVM_TARGET_MAX_CALLS)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Only `BOB` is trusted to call EVM from an arbitrary source address.
+pub struct TrustedEvmSourceCallers;
+impl Contains<AccountId> for TrustedEvmSourceCallers {
+    fn contains(account: &AccountId) -> bool {
+        *account == BOB
+    }
+}
+
+parameter_types! {
+    pub EvmCustomErrors: Vec<EvmCustomErrorSignature> = vec![EvmCustomErrorSignature::new(
+        b"InsufficientBalance",
+        vec![EvmAbiType::Address, EvmAbiType::Uint256],
+    )];
+    // ERC20 `transfer(address,uint256)` to PSP22 `transfer`.
+    pub SelectorMap: Vec<([u8; 4], [u8; 4])> = vec![([0xa9, 0x05, 0x9c, 0xbb], [0xdb, 0x20, 0xf9, 0xf5])];
+    pub static BatchSummaryEvent: bool = true;
+    pub static MaxEvmOutputSize: u32 = 8;
+    pub static TruncateOversizedOutput: bool = false;
+    pub static RequireExplicitGasLimit: bool = false;
+    pub static DenySelfCalls: bool = false;
+    pub static WeightExhaustionThreshold: Option<Perbill> = None;
+    pub const XvmPalletId: PalletId = PalletId(*b"mokxvmac");
+    pub static MaxTransferValue: Option<Balance> = None;
+    pub static TargetExistentialDepositPolicy: ExistentialDepositPolicy = ExistentialDepositPolicy::Allow;
+}
+
 impl pallet_xvm::Config for TestRuntime {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
     type GasWeightMapping = MockGasWeightMapping;
+    type PalletId = XvmPalletId;
+    type PrimaryAccountMapping = PrimaryAccountMapping;
     type AccountMapping = HashedAccountMapping;
     type EthereumTransact = MockEthereumTransact;
+    type MaxAccessListEntries = ConstU32<4>;
+    type MaxEvmOutputSize = MaxEvmOutputSize;
+    type MaxWasmOutputSize = ConstU32<1024>;
+    type TruncateOversizedOutput = TruncateOversizedOutput;
+    type RequireExplicitGasLimit = RequireExplicitGasLimit;
+    type DenySelfCalls = DenySelfCalls;
+    type MaxTransferValue = MaxTransferValue;
+    type TargetExistentialDepositPolicy = TargetExistentialDepositPolicy;
+    type TrustedEvmSourceCallers = TrustedEvmSourceCallers;
+    type TrustedRawEvmTxCallers = TrustedEvmSourceCallers;
+    type IsPrecompile = Nothing;
+    type AllowedEvmTargets = AllowedEvmTargets;
+    type EvmCustomErrors = EvmCustomErrors;
+    type SelectorMap = SelectorMap;
+    type BatchSummaryEvent = BatchSummaryEvent;
+    type WeightExhaustionThreshold = WeightExhaustionThreshold;
+    type MaxDeferredCalls = ConstU32<2>;
+    type MaxDeferredDepth = ConstU32<1>;
+    type PauseOrigin = EnsureRoot<AccountId>;
+    type MaxInputSize = ConstU32<8>;
+    type StagedInputLifetime = ConstU64<10>;
+    type StagedInputDepositPerByte = ConstU128<1>;
+    type MaxCallsPerBlock = ConstU32<100>;
+    type MaxCallsPerTarget = MockMaxCallsPerTarget;
     type WeightInfo = weights::SubstrateWeight<TestRuntime>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = MockEthereumTransact;
 }
 
 pub(crate) type AccountId = AccountId32;
@@ -208,22 +381,64 @@ construct_runtime!(
 );
 
 pub(crate) const ALICE: AccountId = AccountId32::new([0u8; 32]);
+pub(crate) const ALICE_BALANCE: Balance = 1_000_000_000_000;
+pub(crate) const BOB: AccountId = AccountId32::new([1u8; 32]);
 
 #[derive(Default)]
-pub struct ExtBuilder;
+pub struct ExtBuilder {
+    paused: bool,
+}
 
 impl ExtBuilder {
+    #[allow(dead_code)]
+    pub fn paused(mut self, paused: bool) -> Self {
+        self.paused = paused;
+        self
+    }
+
     #[allow(dead_code)]
     pub fn build(self) -> TestExternalities {
         TRANSACTED.with(|v| *v.borrow_mut() = None);
+        NONCE.with(|v| *v.borrow_mut() = U256::zero());
+        TRANSACT_ERROR.with(|v| *v.borrow_mut() = None);
+        EVM_BALANCES.with(|v| v.borrow_mut().clear());
+        IN_XVM_ON_TRANSACT.with(|v| *v.borrow_mut() = None);
+        PANIC_ON_TRANSACT.with(|v| *v.borrow_mut() = false);
+        BatchSummaryEvent::set(true);
+        MaxEvmOutputSize::set(8);
+        TruncateOversizedOutput::set(false);
+        RequireExplicitGasLimit::set(false);
+        MaxTransferValue::set(None);
+        TargetExistentialDepositPolicy::set(ExistentialDepositPolicy::Allow);
+        OUTPUT.with(|v| v.borrow_mut().clear());
 
-        let t = frame_system::GenesisConfig::default()
+        let mut t = frame_system::GenesisConfig::default()
             .build_storage::<TestRuntime>()
             .unwrap();
+        pallet_balances::GenesisConfig::<TestRuntime> {
+            balances: vec![(ALICE, ALICE_BALANCE)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+        <pallet_xvm::GenesisConfig as GenesisBuild<TestRuntime>>::assimilate_storage(
+            &pallet_xvm::GenesisConfig {
+                paused: self.paused,
+            },
+            &mut t,
+        )
+        .unwrap();
 
         let mut ext = TestExternalities::from(t);
         ext.execute_with(|| {
             System::set_block_number(1);
+            // Like `pallet_evm`, the EVM balance of `ALICE` doesn't include the existential
+            // deposit.
+            EVM_BALANCES.with(|v| {
+                v.borrow_mut().insert(
+                    Xvm::mapped_evm_address(ALICE),
+                    (ALICE_BALANCE - <Balances as Currency<AccountId>>::minimum_balance()).into(),
+                )
+            });
         });
         ext
     }
@@ -0,0 +1,52 @@
+// This file is part of Astar.
+
+// Copyright (C) 2019-2023 Stake Technologies Pte.Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Astar is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Astar is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Astar. If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API for off-chain XVM call simulation and account mapping queries.
+
+use astar_primitives::{
+    xvm::{Context, VmId, XvmSimulationResult},
+    Balance,
+};
+use parity_scale_codec::Codec;
+use sp_core::H160;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+    pub trait XvmRuntimeApi<AccountId>
+    where
+        AccountId: Codec,
+    {
+        /// Simulate an XVM call, without committing any state changes.
+        fn xvm_call(
+            context: Context,
+            vm_id: VmId,
+            source: AccountId,
+            target: Vec<u8>,
+            input: Vec<u8>,
+            value: Balance,
+        ) -> XvmSimulationResult;
+
+        /// The `H160` address XVM calls into EVM from `account` are executed from.
+        ///
+        /// There's no inverse, as the account mapping is one-way.
+        fn xvm_account_mapping(account: AccountId) -> H160;
+
+        /// The VMs XVM calls can target, for clients to avoid calls into unsupported VMs.
+        fn xvm_supported_vms() -> Vec<VmId>;
+    }
+}
@@ -0,0 +1,221 @@
+// This file is part of Astar.
+
+// Copyright (C) 2019-2023 Stake Technologies Pte.Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Astar is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Astar is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Astar. If not, see <http://www.gnu.org/licenses/>.
+
+//! Utilities to test cross-VM calls end-to-end, for runtimes with XVM.
+//!
+//! Only available with the `test-utils` feature.
+
+use super::*;
+
+use fp_evm::{CallInfo, ExitSucceed, UsedGas};
+use pallet_contracts_primitives::Code;
+use pallet_evm::Runner;
+use sp_runtime::traits::Zero;
+use sp_std::cell::RefCell;
+
+/// Deploy an EVM contract with init `code` from `deployer`, returning its address.
+///
+/// Panics if the deployment fails.
+pub fn deploy_evm_contract<T: pallet_evm::Config>(deployer: H160, code: Vec<u8>) -> H160 {
+    let info = T::Runner::create(
+        deployer,
+        code,
+        U256::zero(),
+        10_000_000,
+        None,
+        None,
+        None,
+        Vec::new(),
+        false,
+        false,
+        None,
+        None,
+        T::config(),
+    )
+    .map_err(|e| e.error.into())
+    .unwrap_or_else(|e: DispatchError| panic!("EVM contract deployment failed: {:?}", e));
+    assert!(
+        matches!(info.exit_reason, ExitReason::Succeed(_)),
+        "EVM contract deployment failed: {:?}",
+        info.exit_reason
+    );
+    info.value
+}
+
+/// Deploy a WASM contract with `code` from `deployer`, calling its constructor with `data`,
+/// returning its address.
+///
+/// Panics if the deployment fails or reverts.
+pub fn deploy_wasm_contract<T: pallet_contracts::Config>(
+    deployer: T::AccountId,
+    code: Vec<u8>,
+    data: Vec<u8>,
+) -> T::AccountId {
+    let outcome = pallet_contracts::Pallet::<T>::bare_instantiate(
+        deployer,
+        Zero::zero(),
+        Weight::from_parts(10_000_000_000, 1024 * 1024),
+        None,
+        Code::Upload(code),
+        data,
+        Vec::new(),
+        DebugInfo::Skip,
+        CollectEvents::Skip,
+    );
+    let result = outcome
+        .result
+        .unwrap_or_else(|e| panic!("WASM contract deployment failed: {:?}", e));
+    assert!(
+        !result.result.did_revert(),
+        "WASM contract deployment reverted: {:?}",
+        result.result
+    );
+    result.account_id
+}
+
+/// Builder of a XVM call from one VM into a contract of the other.
+pub struct XvmCallBuilder<T: Config> {
+    context: Context,
+    vm_id: VmId,
+    source: T::AccountId,
+    target: Vec<u8>,
+    input: Vec<u8>,
+    value: Balance,
+    storage_deposit_limit: Option<Balance>,
+}
+
+impl<T: Config> XvmCallBuilder<T> {
+    /// A call from WASM into the EVM contract `target`.
+    pub fn evm(source: T::AccountId, target: H160) -> Self {
+        Self::new(VmId::Wasm, VmId::Evm, source, target.encode())
+    }
+
+    /// A call from EVM into the WASM contract `target`.
+    pub fn wasm(source: T::AccountId, target: T::AccountId) -> Self {
+        Self::new(VmId::Evm, VmId::Wasm, source, target.encode())
+    }
+
+    fn new(source_vm_id: VmId, vm_id: VmId, source: T::AccountId, target: Vec<u8>) -> Self {
+        Self {
+            context: Context {
+                source_vm_id,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            vm_id,
+            source,
+            target,
+            input: Vec::new(),
+            value: 0,
+            storage_deposit_limit: None,
+        }
+    }
+
+    /// Call input data, empty by default.
+    pub fn input(mut self, input: Vec<u8>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Value to transfer, zero by default.
+    pub fn value(mut self, value: Balance) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// Weight limit of the call.
+    pub fn weight_limit(mut self, weight_limit: Weight) -> Self {
+        self.context.weight_limit = weight_limit;
+        self
+    }
+
+    /// Storage deposit limit of WASM calls, unlimited by default.
+    pub fn storage_deposit_limit(mut self, storage_deposit_limit: Balance) -> Self {
+        self.storage_deposit_limit = Some(storage_deposit_limit);
+        self
+    }
+
+    /// Make the call.
+    pub fn call(self) -> CallResult {
+        Pallet::<T>::call(
+            self.context,
+            self.vm_id,
+            self.source,
+            self.target,
+            self.input,
+            self.value,
+            self.storage_deposit_limit,
+        )
+    }
+}
+
+thread_local! {
+    static SCRIPTED_OUTCOME: RefCell<(ExitReason, Vec<u8>)> =
+        RefCell::new((ExitReason::Succeed(ExitSucceed::Returned), Vec::new()));
+}
+
+/// A `CheckedEthereumTransact` returning a scripted outcome without executing the EVM, to
+/// test how XVM handles EVM results.
+///
+/// Succeeds with empty output until scripted with `script`. The script is per thread, and so
+/// per test.
+pub struct ScriptedEthereumTransact;
+
+impl ScriptedEthereumTransact {
+    /// Make the next transactions exit with `exit_reason` and return `value`, the output or
+    /// the revert data.
+    pub fn script(exit_reason: ExitReason, value: Vec<u8>) {
+        SCRIPTED_OUTCOME.with(|outcome| *outcome.borrow_mut() = (exit_reason, value));
+    }
+}
+
+impl CheckedEthereumTransact for ScriptedEthereumTransact {
+    fn xvm_transact(
+        _source: H160,
+        _checked_tx: CheckedEthereumTx,
+    ) -> Result<(PostDispatchInfo, CallInfo), DispatchErrorWithPostInfo> {
+        let (exit_reason, value) = SCRIPTED_OUTCOME.with(|outcome| outcome.borrow().clone());
+        Ok((
+            PostDispatchInfo {
+                actual_weight: Some(Weight::zero()),
+                pays_fee: Default::default(),
+            },
+            CallInfo {
+                exit_reason,
+                value,
+                used_gas: UsedGas {
+                    standard: U256::zero(),
+                    effective: U256::zero(),
+                },
+                weight_info: None,
+                logs: Vec::new(),
+            },
+        ))
+    }
+
+    fn account_nonce(_source: H160) -> U256 {
+        U256::zero()
+    }
+
+    fn account_balance(_address: H160) -> U256 {
+        U256::zero()
+    }
+}
@@ -21,24 +21,40 @@
 use super::*;
 use mock::*;
 
-use frame_support::{assert_noop, assert_ok, weights::Weight};
-use parity_scale_codec::Encode;
-use sp_core::H160;
-use sp_runtime::MultiAddress;
+use astar_primitives::xvm::{
+    call_status, ContextBuilder, EvmAbiValue, ExitCategory, WeightBreakdown,
+};
+use fp_evm::{ExitFatal, ExitRevert, ExitSucceed};
+use frame_support::{
+    assert_err, assert_noop, assert_ok,
+    dispatch::{DispatchErrorWithPostInfo, PostDispatchInfo},
+    traits::Hooks,
+    weights::Weight,
+};
+use parity_scale_codec::{Decode, Encode};
+use sp_core::{H160, H256};
+use sp_runtime::{traits::Dispatchable, DispatchError, MultiAddress};
+
+/// Context of calls from `source_vm_id`, with the test weight limit.
+fn context_from(source_vm_id: VmId) -> Context {
+    ContextBuilder::new(source_vm_id)
+        .weight_limit(Weight::from_parts(1_000_000, 1_000_000))
+        .build()
+        .expect("the weight limit is set")
+}
 
 #[test]
 fn calling_into_same_vm_is_not_allowed() {
     ExtBuilder::default().build().execute_with(|| {
         // Calling EVM from EVM
-        let evm_context = Context {
-            source_vm_id: VmId::Evm,
-            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
-        };
+        let evm_context = context_from(VmId::Evm);
         let evm_vm_id = VmId::Evm;
         let evm_target = H160::repeat_byte(1).encode();
         let input = vec![1, 2, 3];
         let value = 1_000_000u128;
-        let evm_used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
         assert_noop!(
             Xvm::call(
                 evm_context,
@@ -49,18 +65,14 @@ fn calling_into_same_vm_is_not_allowed() {
                 value,
                 None
             ),
-            CallFailure::error(SameVmCallDenied, evm_used_weight,),
+            CallFailure::denied(SameVmCallDenied, evm_overheads, denial_weight),
         );
 
         // Calling WASM from WASM
-        let wasm_context = Context {
-            source_vm_id: VmId::Wasm,
-            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
-        };
+        let wasm_context = context_from(VmId::Wasm);
         let wasm_vm_id = VmId::Wasm;
         let wasm_target = ALICE.encode();
-        let wasm_used_weight: Weight =
-            weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads();
+        let wasm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads();
         assert_noop!(
             Xvm::call(
                 wasm_context,
@@ -71,18 +83,37 @@ fn calling_into_same_vm_is_not_allowed() {
                 value,
                 None
             ),
-            CallFailure::error(SameVmCallDenied, wasm_used_weight,),
+            CallFailure::denied(SameVmCallDenied, wasm_overheads, denial_weight),
         );
     });
 }
 
+#[test]
+fn reentrance_denied_call_refunds_weight() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(1).encode();
+        let overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+
+        // Simulate being in a XVM call already.
+        IN_XVM.with(|in_xvm| in_xvm.replace(true));
+        let result = Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None);
+        IN_XVM.with(|in_xvm| in_xvm.take());
+
+        let failure = result.expect_err("reentrance must be denied");
+        assert_eq!(failure.reason, FailureReason::Error(ReentranceDenied));
+        assert_eq!(failure.used_weight, denial_weight);
+        assert_eq!(failure.refunded_weight, overheads - denial_weight);
+        assert_eq!(failure.used_weight + failure.refunded_weight, overheads);
+    });
+}
+
 #[test]
 fn evm_call_fails_if_target_not_h160() {
     ExtBuilder::default().build().execute_with(|| {
-        let context = Context {
-            source_vm_id: VmId::Wasm,
-            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
-        };
+        let context = context_from(VmId::Wasm);
         let vm_id = VmId::Evm;
         let input = vec![1; 65_536];
         let value = 1_000_000u128;
@@ -93,7 +124,7 @@ fn evm_call_fails_if_target_not_h160() {
                 context.clone(),
                 vm_id,
                 ALICE,
-                ALICE.encode(),
+                vec![1; 21],
                 input.clone(),
                 value,
                 None
@@ -108,13 +139,61 @@ fn evm_call_fails_if_target_not_h160() {
     });
 }
 
+#[test]
+fn evm_call_fails_if_target_encoded_for_wasm() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        // An encoded account id is a WASM target, not a malformed EVM one.
+        assert_noop!(
+            Xvm::call(
+                context,
+                VmId::Evm,
+                ALICE,
+                ALICE.encode(),
+                vec![1; 65_536],
+                1_000_000,
+                None
+            ),
+            CallFailure::revert(TargetVmMismatch, used_weight,),
+        );
+    });
+}
+
+#[test]
+fn wasm_call_fails_if_target_not_account_id() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Evm);
+        let vm_id = VmId::Wasm;
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                vm_id,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                input.clone(),
+                value,
+                None
+            ),
+            CallFailure::revert(TargetVmMismatch, used_weight,),
+        );
+
+        assert_noop!(
+            Xvm::call(context, vm_id, ALICE, vec![1; 31], input, value, None),
+            CallFailure::revert(InvalidTarget, used_weight,),
+        );
+    });
+}
+
 #[test]
 fn evm_call_fails_if_input_too_large() {
     ExtBuilder::default().build().execute_with(|| {
-        let context = Context {
-            source_vm_id: VmId::Wasm,
-            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
-        };
+        let context = context_from(VmId::Wasm);
         let vm_id = VmId::Evm;
         let target = H160::repeat_byte(0xFF);
         let value = 1_000_000u128;
@@ -135,13 +214,71 @@ fn evm_call_fails_if_input_too_large() {
     });
 }
 
+#[test]
+fn dry_run_call_fails_if_invalid() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let vm_id = VmId::Evm;
+        let value = 1_000_000u128;
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_noop!(
+            Xvm::call_with_dry_run(
+                context.clone(),
+                vm_id,
+                ALICE,
+                vec![1, 2, 3],
+                vec![1, 2, 3],
+                value,
+                None,
+                true
+            ),
+            CallFailure::revert(InvalidTarget, used_weight),
+        );
+        assert_noop!(
+            Xvm::call_with_dry_run(
+                context,
+                vm_id,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![1; 65_537],
+                value,
+                None,
+                true
+            ),
+            CallFailure::revert(InputTooLarge, used_weight),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+    });
+}
+
+#[test]
+fn dry_run_call_returns_overheads_without_execution() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        let output = Xvm::call_with_dry_run(
+            context,
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            vec![1, 2, 3],
+            1_000_000,
+            None,
+            true,
+        )
+        .expect("dry run failed");
+        assert_eq!(output.used_weight, used_weight);
+        assert!(output.output.is_empty());
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+    });
+}
+
 #[test]
 fn evm_call_works() {
     ExtBuilder::default().build().execute_with(|| {
-        let context = Context {
-            source_vm_id: VmId::Wasm,
-            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
-        };
+        let context = context_from(VmId::Wasm);
         let vm_id = VmId::Evm;
         let target = H160::repeat_byte(0xFF);
         let input = vec![1; 65_536];
@@ -178,10 +315,7 @@ fn evm_call_works() {
 #[test]
 fn wasm_call_fails_if_invalid_target() {
     ExtBuilder::default().build().execute_with(|| {
-        let context = Context {
-            source_vm_id: VmId::Evm,
-            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
-        };
+        let context = context_from(VmId::Evm);
         let vm_id = VmId::Wasm;
         let target = vec![1, 2, 3];
         let input = vec![1, 2, 3];
@@ -194,3 +328,2758 @@ fn wasm_call_fails_if_invalid_target() {
         );
     });
 }
+
+#[test]
+fn wasm_call_fails_if_value_cannot_cover_storage_deposit() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = Context {
+            storage_deposit_from_value: true,
+            ..context_from(VmId::Evm)
+        };
+        let target = AccountId::new([1u8; 32]).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Wasm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                100,
+                Some(101)
+            ),
+            CallFailure::error(OutOfStorageDeposit, used_weight),
+        );
+        // The storage deposit limit is required.
+        assert_noop!(
+            Xvm::call(context, VmId::Wasm, ALICE, target, vec![1, 2, 3], 100, None),
+            CallFailure::error(OutOfStorageDeposit, used_weight),
+        );
+    });
+}
+
+#[test]
+fn call_with_nonce_works_if_nonce_matches() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+
+        MockEthereumTransact::set_nonce(U256::from(7));
+        assert_eq!(Xvm::evm_nonce(ALICE), U256::from(7));
+
+        assert_ok!(Xvm::call_with_nonce(
+            context.clone(),
+            ALICE,
+            target.encode(),
+            input.clone(),
+            value,
+            Some(U256::from(7)),
+        ));
+        // Nonce check is optional.
+        assert_ok!(Xvm::call_with_nonce(
+            context,
+            ALICE,
+            target.encode(),
+            input,
+            value,
+            None,
+        ));
+    });
+}
+
+#[test]
+fn call_with_nonce_fails_if_nonce_mismatch() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads()
+            .saturating_add(<TestRuntime as frame_system::Config>::DbWeight::get().reads(1));
+
+        MockEthereumTransact::set_nonce(U256::from(7));
+        assert_noop!(
+            Xvm::call_with_nonce(
+                context,
+                ALICE,
+                target.encode(),
+                vec![1, 2, 3],
+                1_000_000u128,
+                Some(U256::from(6)),
+            ),
+            CallFailure::error(NonceMismatch, used_weight),
+        );
+    });
+}
+
+#[test]
+fn evm_call_with_access_list_works_at_max_entries() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+        // 2 addresses + 2 storage keys, equals to `MaxAccessListEntries`.
+        let access_list = vec![
+            (H160::repeat_byte(1), vec![H256::repeat_byte(1)]),
+            (H160::repeat_byte(2), vec![H256::repeat_byte(2)]),
+        ];
+
+        assert_ok!(Xvm::call_with_access_list(
+            context,
+            ALICE,
+            target.encode(),
+            input.clone(),
+            value,
+            access_list.clone(),
+        ));
+        MockEthereumTransact::assert_transacted(
+            HashedAccountMapping::into_h160(ALICE),
+            CheckedEthereumTx {
+                gas_limit: U256::from(246000),
+                target,
+                value: U256::from(value),
+                input: EthereumTxInput::try_from(input).expect("input too large"),
+                maybe_access_list: Some(access_list),
+            },
+        );
+    });
+}
+
+#[test]
+fn evm_call_fails_if_access_list_too_large() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        // 2 addresses + 3 storage keys, exceeds `MaxAccessListEntries`.
+        let access_list = vec![
+            (
+                H160::repeat_byte(1),
+                vec![H256::repeat_byte(1), H256::repeat_byte(2)],
+            ),
+            (H160::repeat_byte(2), vec![H256::repeat_byte(3)]),
+        ];
+
+        assert_noop!(
+            Xvm::call_with_access_list(
+                context,
+                ALICE,
+                target.encode(),
+                vec![1, 2, 3],
+                1_000_000u128,
+                access_list,
+            ),
+            CallFailure::revert(AccessListTooLarge, used_weight),
+        );
+    });
+}
+
+#[test]
+fn evm_call_charges_weight_limit_if_transact_error_without_actual_weight() {
+    ExtBuilder::default().build().execute_with(|| {
+        let weight_limit = Weight::from_parts(1_000_000, 1_000_000);
+        let context = Context {
+            weight_limit,
+            ..context_from(VmId::Wasm)
+        };
+        let target = H160::repeat_byte(0xFF);
+        let error = DispatchErrorWithPostInfo {
+            post_info: PostDispatchInfo {
+                actual_weight: None,
+                pays_fee: Default::default(),
+            },
+            error: DispatchError::Other("Failed to validate Ethereum tx"),
+        };
+        MockEthereumTransact::set_error(Some(error.clone()));
+
+        assert_err!(
+            Xvm::call(
+                context,
+                VmId::Evm,
+                ALICE,
+                target.encode(),
+                vec![1, 2, 3],
+                1_000_000u128,
+                None
+            ),
+            CallFailure::error(
+                VmError(format!("EVM call error: {:?}", error.error).into()),
+                weight_limit,
+            ),
+        );
+        assert_eq!(FailedCallCount::<TestRuntime>::get(), 1);
+    });
+}
+
+#[test]
+fn simulate_evm_call_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_eq!(
+            Xvm::simulate_call(context, VmId::Evm, ALICE, target.encode(), input, value),
+            XvmSimulationResult {
+                output: vec![],
+                used_weight,
+                failure: None,
+                revert_reason: None,
+                custom_error: None,
+                top_level_gas_used: None,
+                touched_precompile: None,
+                vm_transition_path: vec![VmId::Wasm, VmId::Evm],
+                weight_diagnostic: None,
+                call_graph: Vec::new(),
+            }
+        );
+    });
+}
+
+#[test]
+fn simulate_call_records_call_graph_if_requested() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = Context {
+            record_call_graph: true,
+            ..context_from(VmId::Wasm)
+        };
+        let target = H160::repeat_byte(0xFF).encode();
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        let result = Xvm::simulate_call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            target.clone(),
+            input.clone(),
+            value,
+        );
+        assert_eq!(
+            result.call_graph,
+            vec![CallFrame {
+                depth: 0,
+                vm_id: VmId::Evm,
+                source: ALICE.encode(),
+                target: target.clone(),
+                value,
+                used_weight,
+                failure: None,
+            }]
+        );
+
+        // Failures are recorded.
+        let result = Xvm::simulate_call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            vec![1, 2, 3],
+            input.clone(),
+            value,
+        );
+        assert_eq!(
+            result.call_graph,
+            vec![CallFrame {
+                depth: 0,
+                vm_id: VmId::Evm,
+                source: ALICE.encode(),
+                target: vec![1, 2, 3],
+                value,
+                used_weight,
+                failure: Some(FailureReason::Revert(InvalidTarget)),
+            }]
+        );
+
+        // Not recorded unless requested.
+        let result = Xvm::simulate_call(
+            Context {
+                record_call_graph: false,
+                ..context.clone()
+            },
+            VmId::Evm,
+            ALICE,
+            target.clone(),
+            input.clone(),
+            value,
+        );
+        assert!(result.call_graph.is_empty());
+
+        // Ignored by on-chain calls.
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target,
+            input,
+            value,
+            None
+        ));
+        assert!(CALL_GRAPH.with(|graph| graph.borrow().is_none()));
+    });
+}
+
+#[test]
+fn simulate_call_reports_failure() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = vec![1, 2, 3];
+        let input = vec![1, 2, 3];
+        let value = 1_000_000u128;
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_eq!(
+            Xvm::simulate_call(context, VmId::Evm, ALICE, target.encode(), input, value),
+            XvmSimulationResult {
+                output: vec![],
+                used_weight,
+                failure: Some(FailureReason::Revert(InvalidTarget)),
+                revert_reason: None,
+                custom_error: None,
+                top_level_gas_used: None,
+                touched_precompile: None,
+                vm_transition_path: vec![VmId::Wasm, VmId::Evm],
+                weight_diagnostic: None,
+                call_graph: Vec::new(),
+            }
+        );
+    });
+}
+
+#[test]
+fn simulate_call_decodes_evm_custom_error() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        // `InsufficientBalance(0x0101..01, 100)` revert data.
+        let mut data = vec![0xf6, 0xde, 0xaa, 0x04];
+        data.extend_from_slice(&[0; 12]);
+        data.extend_from_slice(&[1; 20]);
+        data.extend_from_slice(&[0; 31]);
+        data.push(100);
+        MockEthereumTransact::set_output(data.clone());
+        MockEthereumTransact::set_exit_reason(ExitReason::Revert(ExitRevert::Reverted));
+
+        let result = Xvm::simulate_call(context.clone(), VmId::Evm, ALICE, target, vec![], 0);
+        assert_eq!(
+            result.failure,
+            Some(FailureReason::Revert(VmRevert(data.clone())))
+        );
+        assert_eq!(
+            result.custom_error,
+            Some(EvmCustomError {
+                name: b"InsufficientBalance".to_vec(),
+                params: vec![
+                    EvmAbiValue::Address(H160::repeat_byte(1)),
+                    EvmAbiValue::Uint256(100.into()),
+                ],
+            })
+        );
+
+        // Unknown custom errors aren't decoded.
+        data[0] = 0;
+        MockEthereumTransact::set_output(data);
+        let result = Xvm::simulate_call(
+            context,
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            vec![],
+            0,
+        );
+        assert!(result.failure.is_some());
+        assert_eq!(result.custom_error, None);
+    });
+}
+
+fn mixed_batch() -> Vec<BatchCall> {
+    vec![
+        // Succeeds.
+        BatchCall {
+            vm_id: VmId::Evm,
+            target: H160::repeat_byte(0xFF).encode(),
+            input: vec![1, 2, 3],
+            value: 0,
+            storage_deposit_limit: None,
+        },
+        // Fails with invalid target.
+        BatchCall {
+            vm_id: VmId::Evm,
+            target: vec![1, 2, 3],
+            input: vec![1, 2, 3],
+            value: 0,
+            storage_deposit_limit: None,
+        },
+        // Fails with same VM call denied.
+        BatchCall {
+            vm_id: VmId::Wasm,
+            target: ALICE.encode(),
+            input: vec![1, 2, 3],
+            value: 0,
+            storage_deposit_limit: None,
+        },
+    ]
+}
+
+#[test]
+fn call_batch_emits_summary_event() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = Context {
+            trace_id: [7; 16],
+            ..context_from(VmId::Wasm)
+        };
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+        let batch_overheads: Weight =
+            weights::SubstrateWeight::<TestRuntime>::batch_call_overheads();
+
+        let output = Xvm::call_batch(context, ALICE, mixed_batch());
+        assert_eq!(output.results.len(), 3);
+        assert!(output.results[0].1.is_ok());
+        assert!(output.results[1].1.is_err());
+        assert!(output.results[2].1.is_err());
+
+        System::assert_last_event(RuntimeEvent::Xvm(Event::XvmBatchExecuted {
+            trace_id: [7; 16],
+            count: 3,
+            successes: 1,
+            failures: 2,
+            total_weight: batch_overheads + evm_overheads * 2 + denial_weight,
+        }));
+        assert_eq!(System::events().len(), 1);
+    });
+}
+
+#[test]
+fn call_batch_returns_indexed_results_and_total_weight() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+        let batch_overheads: Weight =
+            weights::SubstrateWeight::<TestRuntime>::batch_call_overheads();
+
+        let output = Xvm::call_batch(context, ALICE, mixed_batch());
+        let outcomes: Vec<(usize, Result<(), FailureReason>)> = output
+            .results
+            .into_iter()
+            .map(|(index, result)| (index, result.map(|_| ()).map_err(|failure| failure.reason)))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (0, Ok(())),
+                (1, Err(FailureReason::Revert(InvalidTarget))),
+                (2, Err(FailureReason::Error(SameVmCallDenied))),
+            ]
+        );
+        // The batch overheads are charged once, on top of the calls.
+        assert_eq!(
+            output.total_weight,
+            batch_overheads + evm_overheads * 2 + denial_weight
+        );
+    });
+}
+
+#[test]
+fn call_batch_with_split_value_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = Context {
+            weight_limit: Weight::from_parts(10_000_000, 10_000_000),
+            ..context_from(VmId::Wasm)
+        };
+        let call = |target: u8, weight: u32| {
+            (
+                BatchCall {
+                    vm_id: VmId::Evm,
+                    target: H160::repeat_byte(target).encode(),
+                    input: vec![1, 2, 3],
+                    // Replaced by the split value.
+                    value: 1_000_000,
+                    storage_deposit_limit: None,
+                },
+                weight,
+            )
+        };
+        let target_balance_after = |result: &CallResult| {
+            result
+                .as_ref()
+                .expect("call failed")
+                .target_balance_after
+                .unwrap_or_default()
+        };
+
+        // Even split.
+        let output = Xvm::call_batch_with_split_value(
+            context.clone(),
+            ALICE,
+            300,
+            vec![call(0x01, 1), call(0x02, 1), call(0x03, 1)],
+        );
+        let values: Vec<U256> = output
+            .results
+            .iter()
+            .map(|(_, result)| target_balance_after(result))
+            .collect();
+        assert_eq!(values, vec![100.into(), 100.into(), 100.into()]);
+
+        // Uneven split with a zero weight target, the remainder goes to the last target.
+        let output = Xvm::call_batch_with_split_value(
+            context,
+            ALICE,
+            100,
+            vec![call(0x04, 1), call(0x05, 0), call(0x06, 2)],
+        );
+        let values: Vec<U256> = output
+            .results
+            .iter()
+            .map(|(_, result)| target_balance_after(result))
+            .collect();
+        assert_eq!(values, vec![33.into(), U256::zero(), 67.into()]);
+    });
+}
+
+#[test]
+fn call_batch_emits_per_call_events_if_summary_disabled() {
+    ExtBuilder::default().build().execute_with(|| {
+        BatchSummaryEvent::set(false);
+        let context = Context {
+            trace_id: [7; 16],
+            ..context_from(VmId::Wasm)
+        };
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+
+        Xvm::call_batch(context, ALICE, mixed_batch());
+
+        let events: Vec<_> = System::events().into_iter().map(|r| r.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                RuntimeEvent::Xvm(Event::XvmCallExecuted {
+                    trace_id: [7; 16],
+                    vm_id: VmId::Evm,
+                    success: true,
+                    used_weight: evm_overheads,
+                }),
+                RuntimeEvent::Xvm(Event::XvmCallExecuted {
+                    trace_id: [7; 16],
+                    vm_id: VmId::Evm,
+                    success: false,
+                    used_weight: evm_overheads,
+                }),
+                RuntimeEvent::Xvm(Event::XvmCallExecuted {
+                    trace_id: [7; 16],
+                    vm_id: VmId::Wasm,
+                    success: false,
+                    used_weight: denial_weight,
+                }),
+            ]
+        );
+    });
+}
+
+#[test]
+fn call_with_output_validation_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        // Mock EVM call returns empty output.
+        assert_eq!(
+            Xvm::call_with_output_validation(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                0,
+                None,
+                astar_primitives::xvm::scale_decodable::<()>,
+            ),
+            Ok(CallOutput::new(vec![], used_weight))
+        );
+
+        assert_eq!(
+            Xvm::call_with_output_validation(
+                context,
+                VmId::Evm,
+                ALICE,
+                target,
+                vec![1, 2, 3],
+                0,
+                None,
+                astar_primitives::xvm::scale_decodable::<u32>,
+            ),
+            Err(CallFailure::revert(OutputDecodeFailed, used_weight))
+        );
+    });
+}
+
+#[test]
+fn evm_call_with_value_returns_target_balance_after() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let value = 1_000_000u128;
+
+        let balance_before = MockEthereumTransact::account_balance(target);
+        let output = Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            target.encode(),
+            vec![1, 2, 3],
+            value,
+            None,
+        )
+        .expect("call should succeed");
+        assert_eq!(
+            output.target_balance_after,
+            Some(balance_before + U256::from(value))
+        );
+
+        // Not set for calls without value.
+        let output = Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target.encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        )
+        .expect("call should succeed");
+        assert_eq!(output.target_balance_after, None);
+    });
+}
+
+#[test]
+fn is_in_xvm_reflects_reentrance_state() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+
+        assert!(!Xvm::is_in_xvm());
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target,
+            vec![1, 2, 3],
+            0,
+            None
+        ));
+        // Set during the call, and reset after.
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+        assert!(!Xvm::is_in_xvm());
+    });
+}
+
+#[test]
+#[should_panic(expected = "transact panicked")]
+fn in_xvm_is_restored_if_inner_call_panics() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        MockEthereumTransact::set_panic_on_transact(true);
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None)
+        }));
+        // Set during the call, and restored while unwinding.
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+        assert!(!Xvm::is_in_xvm());
+
+        if let Err(panic) = result {
+            std::panic::resume_unwind(panic);
+        }
+    });
+}
+
+#[test]
+fn evm_call_fails_if_output_too_large() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        // At max output size.
+        MockEthereumTransact::set_output(vec![1; 8]);
+        assert_eq!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                0,
+                None
+            ),
+            Ok(CallOutput::new(vec![1; 8], used_weight))
+        );
+
+        MockEthereumTransact::set_output(vec![1; 9]);
+        assert_eq!(
+            Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None),
+            Err(CallFailure::revert(OutputTooLarge, used_weight))
+        );
+    });
+}
+
+#[test]
+fn output_too_large_keeps_call_counters() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+
+        MockEthereumTransact::set_output((0..10).collect());
+        assert_eq!(
+            Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None)
+                .map_err(|failure| failure.reason),
+            Err(FailureReason::Revert(OutputTooLarge))
+        );
+
+        // The call state changes are rolled back, but it's still counted.
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 1);
+        assert_eq!(CallsInBlock::<TestRuntime>::get(), 1);
+        assert_eq!(FailedCallCount::<TestRuntime>::get(), 1);
+    });
+}
+
+#[test]
+fn evm_call_truncates_output_if_enabled() {
+    ExtBuilder::default().build().execute_with(|| {
+        TruncateOversizedOutput::set(true);
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+
+        MockEthereumTransact::set_output((0..10).collect());
+        let output = Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None)
+            .expect("call should succeed");
+        assert_eq!(output.output, (0..8).collect::<Vec<u8>>());
+        assert!(output.truncated);
+        assert_eq!(output.original_len, 10);
+    });
+}
+
+#[test]
+fn evm_call_discards_output_if_requested() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = Context {
+            discard_output: true,
+            ..context_from(VmId::Wasm)
+        };
+        let target = H160::repeat_byte(0xFF).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        // Oversized output is discarded before the size check.
+        MockEthereumTransact::set_output((0..10).collect());
+        assert_eq!(
+            Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None),
+            Ok(CallOutput::new(vec![], used_weight)),
+        );
+    });
+}
+
+#[test]
+fn call_fails_early_if_insufficient_balance() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let existential_deposit = <Balances as Currency<AccountId>>::minimum_balance();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                ALICE_BALANCE + 1,
+                None
+            ),
+            CallFailure::error(InsufficientBalance, used_weight),
+        );
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                ALICE_BALANCE - existential_deposit + 1,
+                None
+            ),
+            CallFailure::error(InsufficientBalance, used_weight),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target,
+            vec![1, 2, 3],
+            ALICE_BALANCE - existential_deposit,
+            None
+        ));
+    });
+}
+
+#[test]
+fn wasm_call_fails_early_if_source_would_be_reaped() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Evm);
+        let target = AccountId::new([1u8; 32]).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads();
+        let existential_deposit = <Balances as Currency<AccountId>>::minimum_balance();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Wasm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                ALICE_BALANCE + 1,
+                None
+            ),
+            CallFailure::error(InsufficientBalance, used_weight),
+        );
+        assert_noop!(
+            Xvm::call(
+                context,
+                VmId::Wasm,
+                ALICE,
+                target,
+                vec![1, 2, 3],
+                ALICE_BALANCE - existential_deposit + 1,
+                None
+            ),
+            CallFailure::error(WouldReapAccount, used_weight),
+        );
+    });
+}
+
+#[test]
+fn call_fails_if_value_exceeds_max_transfer_value() {
+    ExtBuilder::default().build().execute_with(|| {
+        let max_transfer_value = 1_000;
+        MaxTransferValue::set(Some(max_transfer_value));
+
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                max_transfer_value + 1,
+                None
+            ),
+            CallFailure::error(ValueTooLarge, used_weight),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+
+        assert_ok!(Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            target.clone(),
+            vec![1, 2, 3],
+            max_transfer_value,
+            None
+        ));
+
+        // No limit if `None`.
+        MaxTransferValue::set(None);
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target,
+            vec![1, 2, 3],
+            max_transfer_value + 1,
+            None
+        ));
+    });
+}
+
+#[test]
+fn call_below_existential_deposit_is_left_to_vm_by_default() {
+    ExtBuilder::default().build().execute_with(|| {
+        let target = H160::repeat_byte(0xFF);
+        let minimum_balance = <TestRuntime as Config>::Currency::minimum_balance();
+
+        assert_ok!(Xvm::call(
+            context_from(VmId::Wasm),
+            VmId::Evm,
+            ALICE,
+            target.encode(),
+            vec![1, 2, 3],
+            minimum_balance - 1,
+            None
+        ));
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+    });
+}
+
+#[test]
+fn call_fails_if_target_below_existential_deposit_with_reject_policy() {
+    ExtBuilder::default().build().execute_with(|| {
+        TargetExistentialDepositPolicy::set(ExistentialDepositPolicy::Reject);
+
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let minimum_balance = <TestRuntime as Config>::Currency::minimum_balance();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.encode(),
+                vec![1, 2, 3],
+                minimum_balance - 1,
+                None
+            ),
+            CallFailure::error(BelowExistentialDeposit, used_weight),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target.encode(),
+            vec![1, 2, 3],
+            minimum_balance,
+            None
+        ));
+        assert_eq!(
+            MockEthereumTransact::account_balance(target),
+            U256::from(minimum_balance)
+        );
+    });
+}
+
+#[test]
+fn call_tops_up_target_to_existential_deposit_with_top_up_policy() {
+    ExtBuilder::default().build().execute_with(|| {
+        TargetExistentialDepositPolicy::set(ExistentialDepositPolicy::TopUp);
+
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let minimum_balance = <TestRuntime as Config>::Currency::minimum_balance();
+
+        assert_ok!(Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            target.encode(),
+            vec![1, 2, 3],
+            minimum_balance - 1,
+            None
+        ));
+        assert_eq!(
+            MockEthereumTransact::account_balance(target),
+            U256::from(minimum_balance)
+        );
+
+        // Not topped up once the target has the existential deposit.
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target.encode(),
+            vec![1, 2, 3],
+            1,
+            None
+        ));
+        assert_eq!(
+            MockEthereumTransact::account_balance(target),
+            U256::from(minimum_balance + 1)
+        );
+    });
+}
+
+#[test]
+fn evm_call_fails_without_explicit_gas_limit_if_required() {
+    ExtBuilder::default().build().execute_with(|| {
+        RequireExplicitGasLimit::set(true);
+
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.encode(),
+                vec![1, 2, 3],
+                0,
+                None
+            ),
+            CallFailure::error(GasLimitRequired, used_weight),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+    });
+}
+
+#[test]
+fn evm_call_with_explicit_gas_limit_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        RequireExplicitGasLimit::set(true);
+
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF);
+        let input = vec![1, 2, 3];
+        let source = HashedAccountMapping::into_h160(ALICE);
+        let checked_tx = |gas_limit: u64| CheckedEthereumTx {
+            gas_limit: U256::from(gas_limit),
+            target,
+            value: U256::zero(),
+            input: EthereumTxInput::try_from(input.clone()).expect("input too large"),
+            maybe_access_list: None,
+        };
+
+        assert_ok!(Xvm::call_with_gas_limit(
+            context.clone(),
+            ALICE,
+            target.encode(),
+            input.clone(),
+            0,
+            100_000
+        ));
+        MockEthereumTransact::assert_transacted(source, checked_tx(100_000));
+
+        // The explicit gas limit is bounded by the weight limit.
+        assert_ok!(Xvm::call_with_gas_limit(
+            context,
+            ALICE,
+            target.encode(),
+            input.clone(),
+            0,
+            1_000_000
+        ));
+        MockEthereumTransact::assert_transacted(source, checked_tx(246_000));
+    });
+}
+
+#[test]
+fn call_counters_are_increased_on_execution() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let evm_target = H160::repeat_byte(0xFF).encode();
+
+        assert_ok!(Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            evm_target.clone(),
+            vec![1, 2, 3],
+            0,
+            None
+        ));
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 1);
+        assert_eq!(WasmCallCount::<TestRuntime>::get(), 0);
+        assert_eq!(FailedCallCount::<TestRuntime>::get(), 0);
+
+        MockEthereumTransact::set_error(Some(DispatchErrorWithPostInfo {
+            post_info: PostDispatchInfo {
+                actual_weight: None,
+                pays_fee: Default::default(),
+            },
+            error: DispatchError::Other("Failed to validate Ethereum tx"),
+        }));
+        assert!(Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            evm_target.clone(),
+            vec![1, 2, 3],
+            0,
+            None
+        )
+        .is_err());
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 2);
+        assert_eq!(FailedCallCount::<TestRuntime>::get(), 1);
+
+        // Calls failed before the execution are not counted.
+        assert!(Xvm::call(context, VmId::Evm, ALICE, vec![1, 2, 3], vec![], 0, None).is_err());
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 2);
+        assert_eq!(FailedCallCount::<TestRuntime>::get(), 1);
+    });
+}
+
+#[test]
+fn in_xvm_is_restored_on_every_return_path() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        let call = |vm_id: VmId, target: Vec<u8>, value: Balance| {
+            Xvm::call(
+                context.clone(),
+                vm_id,
+                ALICE,
+                target,
+                vec![1, 2, 3],
+                value,
+                None,
+            )
+        };
+
+        // success
+        assert_ok!(call(VmId::Evm, target.clone(), 0));
+        assert!(!Xvm::is_in_xvm());
+
+        // denied early
+        assert!(call(VmId::Wasm, ALICE.encode(), 0).is_err());
+        assert!(!Xvm::is_in_xvm());
+        assert!(call(VmId::Evm, vec![1, 2, 3], 0).is_err());
+        assert!(!Xvm::is_in_xvm());
+        assert!(call(VmId::Evm, target.clone(), ALICE_BALANCE + 1).is_err());
+        assert!(!Xvm::is_in_xvm());
+
+        // revert
+        MockEthereumTransact::set_output((0..10).collect());
+        assert!(call(VmId::Evm, target.clone(), 0).is_err());
+        assert!(!Xvm::is_in_xvm());
+        MockEthereumTransact::set_output(vec![]);
+
+        // error
+        MockEthereumTransact::set_error(Some(DispatchErrorWithPostInfo {
+            post_info: PostDispatchInfo {
+                actual_weight: None,
+                pays_fee: Default::default(),
+            },
+            error: DispatchError::Other("Failed to validate Ethereum tx"),
+        }));
+        assert!(call(VmId::Evm, target.clone(), 0).is_err());
+        assert!(!Xvm::is_in_xvm());
+        MockEthereumTransact::set_error(None);
+
+        // Reentrance is denied, and `IN_XVM` of the outer call is kept.
+        IN_XVM.with(|in_xvm| *in_xvm.borrow_mut() = true);
+        assert_noop!(
+            call(VmId::Evm, target, 0),
+            CallFailure::denied(
+                ReentranceDenied,
+                weights::SubstrateWeight::<TestRuntime>::evm_call_overheads(),
+                weights::SubstrateWeight::<TestRuntime>::call_denied_overheads(),
+            ),
+        );
+        assert!(Xvm::is_in_xvm());
+        IN_XVM.with(|in_xvm| *in_xvm.borrow_mut() = false);
+    });
+}
+
+fn deferred_evm_call(value: Balance) -> DeferredXvmCall<AccountId, BlockNumber> {
+    DeferredXvmCall {
+        source_vm_id: VmId::Wasm,
+        vm_id: VmId::Evm,
+        source: ALICE,
+        target: H160::repeat_byte(0xFF).encode(),
+        input: vec![1, 2, 3],
+        value,
+        deadline: None,
+        depth: 0,
+    }
+}
+
+#[test]
+fn defer_call_fails_if_queue_is_full() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Xvm::defer_call(deferred_evm_call(1)));
+        assert_ok!(Xvm::defer_call(deferred_evm_call(2)));
+        assert_noop!(
+            Xvm::defer_call(deferred_evm_call(3)),
+            Error::<TestRuntime>::TooManyDeferredCalls
+        );
+        assert_eq!(
+            DeferredCalls::<TestRuntime>::get().into_inner(),
+            vec![deferred_evm_call(1), deferred_evm_call(2)]
+        );
+    });
+}
+
+#[test]
+fn deferred_calls_are_executed_on_idle() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        assert_ok!(Xvm::defer_call(deferred_evm_call(1)));
+        assert_ok!(Xvm::defer_call(deferred_evm_call(2)));
+
+        // Not enough weight to execute any call.
+        assert_eq!(Xvm::on_idle(1, evm_overheads / 2), Weight::zero());
+        assert_eq!(DeferredCalls::<TestRuntime>::get().len(), 2);
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+
+        // Only the first call fits in the weight.
+        assert_eq!(Xvm::on_idle(1, evm_overheads * 3 / 2), evm_overheads);
+        assert_eq!(
+            DeferredCalls::<TestRuntime>::get().into_inner(),
+            vec![deferred_evm_call(2)]
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+        assert!(!Xvm::is_in_xvm());
+        System::assert_last_event(RuntimeEvent::Xvm(Event::XvmCallExecuted {
+            trace_id: [0; 16],
+            vm_id: VmId::Evm,
+            success: true,
+            used_weight: evm_overheads,
+        }));
+
+        assert_eq!(
+            Xvm::on_idle(1, Weight::from_parts(1_000_000, 1_000_000)),
+            evm_overheads
+        );
+        assert!(DeferredCalls::<TestRuntime>::get().is_empty());
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 2);
+    });
+}
+
+#[test]
+fn weight_breakdown_adds_up() {
+    ExtBuilder::default().build().execute_with(|| {
+        let weight_limit = Weight::from_parts(1_000_000_000, 1_000_000);
+        let context = |source_vm_id| Context {
+            weight_limit,
+            ..context_from(source_vm_id)
+        };
+        let assert_adds_up = |vm_id: VmId, result: &CallResult, overheads: Weight| {
+            let breakdown = WeightBreakdown::new(weight_limit, Xvm::call_overheads(vm_id), result);
+            assert_eq!(breakdown.requested, weight_limit);
+            assert_eq!(breakdown.overheads, overheads);
+            assert_eq!(
+                breakdown.overheads + breakdown.execution,
+                breakdown.total_used
+            );
+            assert_eq!(breakdown.total_used + breakdown.unused(), weight_limit);
+        };
+
+        // EVM
+        let result = Xvm::call(
+            context(VmId::Wasm),
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        );
+        assert!(result.is_ok());
+        assert_adds_up(
+            VmId::Evm,
+            &result,
+            weights::SubstrateWeight::<TestRuntime>::evm_call_overheads(),
+        );
+
+        // WASM, the target isn't a contract.
+        let result = Xvm::call(
+            context(VmId::Evm),
+            VmId::Wasm,
+            ALICE,
+            ALICE.encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        );
+        assert!(result.is_err());
+        assert_adds_up(
+            VmId::Wasm,
+            &result,
+            weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads(),
+        );
+
+        // Denied before entering the VM.
+        let result = Xvm::call(
+            context(VmId::Evm),
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        );
+        assert_adds_up(
+            VmId::Evm,
+            &result,
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads(),
+        );
+    });
+}
+
+#[test]
+fn call_as_evm_address_fails_if_caller_untrusted() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_overheads = weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+
+        assert_noop!(
+            Xvm::call_as_evm_address(
+                context,
+                ALICE,
+                H160::repeat_byte(0xAA),
+                H160::repeat_byte(0xFF).encode(),
+                vec![1, 2, 3],
+                0,
+            ),
+            CallFailure::denied(UntrustedCaller, overheads, denial_overheads),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+    });
+}
+
+#[test]
+fn call_as_evm_address_works_if_caller_trusted() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let source = H160::repeat_byte(0xAA);
+        let input = vec![1, 2, 3];
+        // `BOB` has no balance, the value is transferred from `source`.
+        let value = 1_000u128;
+        MockEthereumTransact::set_balance(source, value);
+
+        assert_ok!(Xvm::call_as_evm_address(
+            context,
+            BOB,
+            source,
+            H160::repeat_byte(0xFF).encode(),
+            input.clone(),
+            value,
+        ));
+        MockEthereumTransact::assert_transacted(
+            source,
+            CheckedEthereumTx {
+                gas_limit: U256::from(246000),
+                target: H160::repeat_byte(0xFF),
+                value: U256::from(value),
+                input: EthereumTxInput::try_from(input).expect("input too large"),
+                maybe_access_list: None,
+            },
+        );
+    });
+}
+
+#[test]
+fn evm_transact_raw_works_if_caller_trusted() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let source = H160::repeat_byte(0xAA);
+        let tx = CheckedEthereumTx {
+            gas_limit: U256::from(246000),
+            target: H160::repeat_byte(0xFF),
+            value: U256::zero(),
+            input: EthereumTxInput::try_from(vec![1, 2, 3]).expect("input too large"),
+            maybe_access_list: None,
+        };
+        let overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_overheads = weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+
+        assert_noop!(
+            Xvm::evm_transact_raw(context.clone(), ALICE, source, tx.clone()),
+            CallFailure::denied(UntrustedCaller, overheads, denial_overheads),
+        );
+
+        assert_ok!(Xvm::evm_transact_raw(context, BOB, source, tx.clone()));
+        MockEthereumTransact::assert_transacted(source, tx);
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+    });
+}
+
+#[test]
+fn evm_transact_raw_fails_if_tx_exceeds_limits() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let tx = CheckedEthereumTx {
+            gas_limit: U256::from(246000),
+            target: H160::repeat_byte(0xFF),
+            value: U256::zero(),
+            input: EthereumTxInput::try_from(vec![1, 2, 3]).expect("input too large"),
+            maybe_access_list: None,
+        };
+        let overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        // The gas limit is above the weight limit.
+        assert_noop!(
+            Xvm::evm_transact_raw(
+                context.clone(),
+                BOB,
+                H160::repeat_byte(0xAA),
+                CheckedEthereumTx {
+                    gas_limit: U256::from(246001),
+                    ..tx.clone()
+                },
+            ),
+            CallFailure::revert(GasLimitTooHigh, overheads),
+        );
+
+        // The access list has too many entries.
+        let max_entries = <TestRuntime as Config>::MaxAccessListEntries::get() as usize;
+        assert_noop!(
+            Xvm::evm_transact_raw(
+                context,
+                BOB,
+                H160::repeat_byte(0xAA),
+                CheckedEthereumTx {
+                    maybe_access_list: Some(vec![(H160::zero(), vec![]); max_entries + 1]),
+                    ..tx
+                },
+            ),
+            CallFailure::revert(AccessListTooLarge, overheads),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+    });
+}
+
+#[test]
+fn evm_transact_raw_checks_the_value_transfer() {
+    ExtBuilder::default().build().execute_with(|| {
+        let source = H160::repeat_byte(0xAA);
+        let tx = CheckedEthereumTx {
+            gas_limit: U256::from(246000),
+            target: H160::repeat_byte(0xFF),
+            value: U256::from(1_000),
+            input: EthereumTxInput::try_from(vec![1, 2, 3]).expect("input too large"),
+            maybe_access_list: None,
+        };
+        let overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_noop!(
+            Xvm::evm_transact_raw(context_from(VmId::Wasm), BOB, source, tx.clone()),
+            CallFailure::error(InsufficientBalance, overheads),
+        );
+
+        MockEthereumTransact::set_balance(source, 1_000);
+        MaxTransferValue::set(Some(999));
+        assert_noop!(
+            Xvm::evm_transact_raw(context_from(VmId::Wasm), BOB, source, tx.clone()),
+            CallFailure::error(ValueTooLarge, overheads),
+        );
+        MaxTransferValue::set(None);
+
+        assert_ok!(Xvm::evm_transact_raw(
+            context_from(VmId::Wasm),
+            BOB,
+            source,
+            tx
+        ));
+    });
+}
+
+#[test]
+fn evm_transact_raw_is_counted_and_rate_limited() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let tx = CheckedEthereumTx {
+            gas_limit: U256::from(246000),
+            target: RATE_LIMITED_EVM_TARGET,
+            value: U256::zero(),
+            input: EthereumTxInput::try_from(vec![1, 2, 3]).expect("input too large"),
+            maybe_access_list: None,
+        };
+        let overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let limited_target = XvmTarget::Evm(RATE_LIMITED_EVM_TARGET);
+
+        for _ in 0..RATE_LIMITED_EVM_TARGET_MAX_CALLS {
+            assert_ok!(Xvm::evm_transact_raw(
+                context.clone(),
+                BOB,
+                H160::repeat_byte(0xAA),
+                tx.clone()
+            ));
+        }
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 2);
+        assert_eq!(CallsInBlock::<TestRuntime>::get(), 2);
+        assert_eq!(
+            Xvm::calls_to_target_in_block(&limited_target),
+            RATE_LIMITED_EVM_TARGET_MAX_CALLS
+        );
+
+        assert_noop!(
+            Xvm::evm_transact_raw(context, BOB, H160::repeat_byte(0xAA), tx),
+            CallFailure::error(TargetRateLimited, overheads),
+        );
+    });
+}
+
+#[test]
+fn calls_are_rejected_while_paused() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let target = H160::repeat_byte(0xFF).encode();
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+
+        assert_noop!(
+            Xvm::set_paused(RuntimeOrigin::signed(ALICE), true),
+            DispatchError::BadOrigin,
+        );
+
+        assert_ok!(Xvm::set_paused(RuntimeOrigin::root(), true));
+        System::assert_last_event(RuntimeEvent::Xvm(Event::Paused));
+        assert_noop!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.clone(),
+                vec![1, 2, 3],
+                0,
+                None
+            ),
+            CallFailure::error(Paused, used_weight),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+
+        assert_ok!(Xvm::set_paused(RuntimeOrigin::root(), false));
+        System::assert_last_event(RuntimeEvent::Xvm(Event::Unpaused));
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            target,
+            vec![1, 2, 3],
+            0,
+            None
+        ));
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+    });
+}
+
+#[test]
+fn genesis_config_sets_paused() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert!(!XvmPaused::<TestRuntime>::get());
+    });
+
+    ExtBuilder::default().paused(true).build().execute_with(|| {
+        assert!(XvmPaused::<TestRuntime>::get());
+        let context = context_from(VmId::Wasm);
+        let used_weight: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        assert_noop!(
+            Xvm::call(
+                context,
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![1, 2, 3],
+                0,
+                None
+            ),
+            CallFailure::error(Paused, used_weight),
+        );
+    });
+}
+
+#[test]
+fn stage_input_appends_chunks() {
+    ExtBuilder::default().build().execute_with(|| {
+        Balances::make_free_balance_be(&BOB, 100);
+        assert_ok!(Xvm::stage_input(
+            RuntimeOrigin::signed(ALICE),
+            vec![1, 2, 3]
+        ));
+        assert_ok!(Xvm::stage_input(RuntimeOrigin::signed(ALICE), vec![4, 5]));
+        assert_ok!(Xvm::stage_input(RuntimeOrigin::signed(BOB), vec![6]));
+
+        let (input, expires_at, deposit) = StagedInputs::<TestRuntime>::get(ALICE).unwrap();
+        assert_eq!(input.into_inner(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(expires_at, 11);
+        assert_eq!(deposit, 5);
+        let (input, _, _) = StagedInputs::<TestRuntime>::get(BOB).unwrap();
+        assert_eq!(input.into_inner(), vec![6]);
+
+        // Exceeding `MaxInputSize`.
+        assert_noop!(
+            Xvm::stage_input(RuntimeOrigin::signed(ALICE), vec![6, 7, 8, 9]),
+            Error::<TestRuntime>::StagedInputTooLarge,
+        );
+    });
+}
+
+#[test]
+fn staged_input_expires() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Xvm::stage_input(
+            RuntimeOrigin::signed(ALICE),
+            vec![1, 2, 3]
+        ));
+
+        System::set_block_number(12);
+        assert_noop!(
+            Xvm::commit_and_call(
+                RuntimeOrigin::signed(ALICE),
+                ALICE.encode(),
+                0,
+                Weight::from_parts(1_000_000, 1_000_000),
+                None,
+            ),
+            Error::<TestRuntime>::NoStagedInput,
+        );
+
+        // An expired input is replaced by the next chunk.
+        assert_ok!(Xvm::stage_input(RuntimeOrigin::signed(ALICE), vec![4]));
+        let (input, expires_at, _) = StagedInputs::<TestRuntime>::get(ALICE).unwrap();
+        assert_eq!(input.into_inner(), vec![4]);
+        assert_eq!(expires_at, 22);
+    });
+}
+
+#[test]
+fn stage_input_reserves_deposit() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Xvm::stage_input(
+            RuntimeOrigin::signed(ALICE),
+            vec![1, 2, 3]
+        ));
+        assert_ok!(Xvm::stage_input(RuntimeOrigin::signed(ALICE), vec![4, 5]));
+        assert_eq!(Balances::reserved_balance(ALICE), 5);
+
+        // The deposit of an expired input is released when it's replaced.
+        System::set_block_number(12);
+        assert_ok!(Xvm::stage_input(RuntimeOrigin::signed(ALICE), vec![6]));
+        assert_eq!(Balances::reserved_balance(ALICE), 1);
+
+        // The deposit can't be reserved without balance.
+        assert_noop!(
+            Xvm::stage_input(RuntimeOrigin::signed(BOB), vec![1]),
+            pallet_balances::Error::<TestRuntime>::InsufficientBalance,
+        );
+    });
+}
+
+#[test]
+fn commit_and_call_keeps_staged_input_if_call_fails() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_noop!(
+            Xvm::commit_and_call(
+                RuntimeOrigin::signed(ALICE),
+                ALICE.encode(),
+                0,
+                Weight::from_parts(1_000_000, 1_000_000),
+                None,
+            ),
+            Error::<TestRuntime>::NoStagedInput,
+        );
+
+        assert_ok!(Xvm::stage_input(
+            RuntimeOrigin::signed(ALICE),
+            vec![1, 2, 3]
+        ));
+        // No contract at the target.
+        let call = RuntimeCall::Xvm(crate::Call::commit_and_call {
+            target: BOB.encode(),
+            value: 0,
+            weight_limit: Weight::from_parts(1_000_000, 1_000_000),
+            storage_deposit_limit: None,
+        });
+        let error = call
+            .dispatch(RuntimeOrigin::signed(ALICE))
+            .expect_err("call should fail");
+        assert_eq!(error.error, Error::<TestRuntime>::XvmCallFailed.into());
+        // Taking the staged input is charged on top of the call.
+        let commit_weight = weights::SubstrateWeight::<TestRuntime>::commit_and_call();
+        assert!(error
+            .post_info
+            .actual_weight
+            .expect("actual weight is set")
+            .any_gt(commit_weight));
+        assert!(StagedInputs::<TestRuntime>::contains_key(ALICE));
+        // The deposit is kept with the input.
+        assert_eq!(Balances::reserved_balance(ALICE), 3);
+    });
+}
+
+#[test]
+fn calls_from_native_source_are_not_same_vm_calls() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let call = |native_source| {
+            Xvm::do_call(
+                context.clone(),
+                VmId::Wasm,
+                ALICE,
+                BOB.encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+                None,
+                None,
+                None,
+                false,
+                native_source,
+            )
+        };
+
+        assert_ne!(
+            call(true).map_err(|failure| failure.reason),
+            Err(FailureReason::Error(SameVmCallDenied))
+        );
+        assert_eq!(
+            call(false).map_err(|failure| failure.reason),
+            Err(FailureReason::Error(SameVmCallDenied))
+        );
+    });
+}
+
+#[test]
+fn every_vm_has_call_overheads() {
+    let vm_ids: Vec<_> = (0..=u8::MAX)
+        .filter_map(|id| VmId::try_from(id).ok())
+        .collect();
+    assert!(!vm_ids.is_empty());
+    for vm_id in vm_ids {
+        assert_ne!(Xvm::call_overheads(vm_id), Weight::zero(), "{:?}", vm_id);
+    }
+}
+
+#[test]
+fn mapped_evm_address_is_the_evm_call_source() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let input = vec![1, 2, 3];
+
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            input.clone(),
+            0,
+            None
+        ));
+        MockEthereumTransact::assert_transacted(
+            Xvm::mapped_evm_address(ALICE),
+            CheckedEthereumTx {
+                gas_limit: U256::from(246000),
+                target: H160::repeat_byte(0xFF),
+                value: U256::zero(),
+                input: EthereumTxInput::try_from(input).expect("input too large"),
+                maybe_access_list: None,
+            },
+        );
+        assert_eq!(
+            Xvm::mapped_evm_address(ALICE),
+            HashedAccountMapping::into_h160(ALICE)
+        );
+    });
+}
+
+#[test]
+fn wasm_call_fails_if_contract_not_found() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = Context {
+            weight_limit: Weight::from_parts(1_000_000_000, 1_000_000),
+            ..context_from(VmId::Evm)
+        };
+
+        let result = Xvm::call(
+            context,
+            VmId::Wasm,
+            ALICE,
+            BOB.encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        );
+        assert_eq!(
+            result.map_err(|failure| failure.reason),
+            Err(FailureReason::Error(ContractNotFound))
+        );
+    });
+}
+
+#[test]
+fn weight_limit_from_gas_round_trips() {
+    ExtBuilder::default().build().execute_with(|| {
+        for gas in [0u64, 1, 21_000, 246_000, 10_000_000] {
+            let weight_limit = Xvm::weight_limit_from_gas(U256::from(gas));
+            assert_eq!(MockGasWeightMapping::weight_to_gas(weight_limit), gas);
+            assert_eq!(MockGasWeightMapping::gas_to_weight(gas, true), weight_limit);
+        }
+
+        // Saturating at the max block weight.
+        let max_block = <TestRuntime as frame_system::Config>::BlockWeights::get().max_block;
+        assert_eq!(
+            Xvm::weight_limit_from_gas(U256::MAX).ref_time(),
+            max_block.ref_time()
+        );
+        assert_eq!(
+            Xvm::weight_limit_from_gas(U256::from(u64::MAX)),
+            Xvm::weight_limit_from_gas(U256::MAX)
+        );
+    });
+}
+
+#[test]
+fn evm_call_returns_used_gas() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        MockEthereumTransact::set_used_gas(U256::from(21_042));
+        let output = Xvm::call(
+            context,
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        )
+        .unwrap();
+        assert_eq!(output.used_gas, Some(U256::from(21_042)));
+        assert_eq!(output.storage_deposit, None);
+    });
+}
+
+#[test]
+fn evm_call_reports_success_status() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let call = || {
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+            )
+        };
+
+        for (reason, status) in [
+            (ExitSucceed::Returned, call_status::RETURNED),
+            (ExitSucceed::Stopped, call_status::STOPPED),
+            (ExitSucceed::Suicided, call_status::SUICIDED),
+        ] {
+            MockEthereumTransact::set_exit_reason(ExitReason::Succeed(reason));
+            assert_eq!(call().map(|output| output.status), Ok(status));
+        }
+    });
+}
+
+#[test]
+fn calls_beyond_block_limit_are_rejected() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let overheads = Xvm::call_overheads(VmId::Evm);
+        let call = || {
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![],
+                0,
+                None,
+            )
+            .map_err(|failure| failure.reason)
+        };
+
+        // Denied calls don't count towards the limit.
+        assert_noop!(
+            Xvm::call(
+                Context {
+                    source_vm_id: VmId::Evm,
+                    ..context.clone()
+                },
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![],
+                0,
+                None,
+            ),
+            CallFailure::denied(
+                SameVmCallDenied,
+                overheads,
+                weights::SubstrateWeight::<TestRuntime>::call_denied_overheads()
+            ),
+        );
+
+        let max_calls = <TestRuntime as Config>::MaxCallsPerBlock::get();
+        for _ in 0..max_calls {
+            assert_ok!(call());
+        }
+        assert_eq!(CallsInBlock::<TestRuntime>::get(), max_calls);
+        assert_err!(
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![],
+                0,
+                None,
+            ),
+            CallFailure::error(CallLimitReached, overheads),
+        );
+
+        // Reset in the next block.
+        Xvm::on_initialize(2);
+        assert_eq!(CallsInBlock::<TestRuntime>::get(), 0);
+        assert_ok!(call());
+    });
+}
+
+#[test]
+fn calls_beyond_target_limit_are_rejected() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let overheads = Xvm::call_overheads(VmId::Evm);
+        let call = |target: H160| {
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.encode(),
+                vec![],
+                0,
+                None,
+            )
+        };
+        let limited_target = XvmTarget::Evm(RATE_LIMITED_EVM_TARGET);
+        let other_target = XvmTarget::Evm(H160::repeat_byte(0xFF));
+
+        for _ in 0..RATE_LIMITED_EVM_TARGET_MAX_CALLS {
+            assert_ok!(call(RATE_LIMITED_EVM_TARGET));
+        }
+        assert_eq!(
+            Xvm::calls_to_target_in_block(&limited_target),
+            RATE_LIMITED_EVM_TARGET_MAX_CALLS
+        );
+        assert_noop!(
+            call(RATE_LIMITED_EVM_TARGET),
+            CallFailure::error(TargetRateLimited, overheads),
+        );
+
+        // Other targets remain callable, and aren't counted.
+        assert_ok!(call(H160::repeat_byte(0xFF)));
+        assert!(!CallsToTargetInBlock::<TestRuntime>::contains_key(
+            &other_target
+        ));
+
+        // Reset in the next block, without clearing the counter.
+        System::set_block_number(2);
+        Xvm::on_initialize(2);
+        assert!(CallsToTargetInBlock::<TestRuntime>::contains_key(
+            &limited_target
+        ));
+        assert_eq!(Xvm::calls_to_target_in_block(&limited_target), 0);
+        assert_ok!(call(RATE_LIMITED_EVM_TARGET));
+    });
+}
+
+#[test]
+fn estimated_call_overheads_match_default_weights() {
+    for vm_id in [VmId::Evm, VmId::Wasm] {
+        assert_eq!(
+            weights::estimated_call_overheads(vm_id),
+            weights::call_overheads::<()>(vm_id)
+        );
+        // The mock runtime weights match the default ones.
+        assert_eq!(
+            weights::estimated_call_overheads(vm_id),
+            Xvm::call_overheads(vm_id)
+        );
+    }
+    assert_eq!(
+        weights::estimated_call_overheads(VmId::Evm),
+        <() as WeightInfo>::evm_call_overheads()
+    );
+}
+
+#[test]
+fn translate_selector_works() {
+    // Mapped selector is rewritten, the rest of the input is kept.
+    assert_eq!(
+        Xvm::translate_selector(vec![0xa9, 0x05, 0x9c, 0xbb, 1, 2, 3]),
+        vec![0xdb, 0x20, 0xf9, 0xf5, 1, 2, 3],
+    );
+    assert_eq!(
+        Xvm::translate_selector(vec![0xa9, 0x05, 0x9c, 0xbb]),
+        vec![0xdb, 0x20, 0xf9, 0xf5],
+    );
+
+    // Unmapped selectors and short inputs are passed through.
+    assert_eq!(
+        Xvm::translate_selector(vec![0xdb, 0x20, 0xf9, 0xf5, 1]),
+        vec![0xdb, 0x20, 0xf9, 0xf5, 1],
+    );
+    assert_eq!(
+        Xvm::translate_selector(vec![0xa9, 0x05, 0x9c]),
+        vec![0xa9, 0x05, 0x9c],
+    );
+    assert_eq!(Xvm::translate_selector(vec![]), Vec::<u8>::new());
+}
+
+#[test]
+fn call_many_uses_independent_contexts() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+        let spec = |source_vm_id: VmId, weight_limit: Weight| XvmCallSpec {
+            context: Context {
+                weight_limit,
+                ..context_from(source_vm_id)
+            },
+            vm_id: VmId::Evm,
+            target: H160::repeat_byte(0xFF).encode(),
+            input: vec![1, 2, 3],
+            value: 0,
+            storage_deposit_limit: None,
+        };
+
+        let results = Xvm::call_many(
+            ALICE,
+            vec![
+                spec(VmId::Wasm, Weight::from_parts(1_000_000, 1_000_000)),
+                // Fails with same VM call denied, the next call is still executed.
+                spec(VmId::Evm, Weight::from_parts(1_000_000, 1_000_000)),
+                spec(VmId::Wasm, Weight::from_parts(500_000, 500_000)),
+            ],
+        );
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            results[0].as_ref().map(|output| output.used_weight),
+            Ok(evm_overheads)
+        );
+        assert_eq!(
+            results[1],
+            Err(CallFailure::denied(
+                SameVmCallDenied,
+                evm_overheads,
+                denial_weight
+            ))
+        );
+        assert_eq!(
+            results[2].as_ref().map(|output| output.used_weight),
+            Ok(evm_overheads)
+        );
+        assert!(!Xvm::is_in_xvm());
+    });
+}
+
+#[test]
+fn call_many_denies_reentrance() {
+    ExtBuilder::default().build().execute_with(|| {
+        let spec = XvmCallSpec {
+            context: context_from(VmId::Wasm),
+            vm_id: VmId::Evm,
+            target: H160::repeat_byte(0xFF).encode(),
+            input: vec![1, 2, 3],
+            value: 0,
+            storage_deposit_limit: None,
+        };
+
+        // Simulate being in a XVM call already.
+        IN_XVM.with(|in_xvm| in_xvm.replace(true));
+        let results = Xvm::call_many(ALICE, vec![spec.clone(), spec]);
+        IN_XVM.with(|in_xvm| in_xvm.take());
+
+        assert_eq!(results.len(), 2);
+        for result in results {
+            assert_eq!(
+                result.map_err(|failure| failure.reason),
+                Err(FailureReason::Error(ReentranceDenied))
+            );
+        }
+    });
+}
+
+/// An EVM call spec of `call_conditional` tests, and the expected transaction.
+fn conditional_evm_call(target: H160) -> (XvmCallSpec, CheckedEthereumTx) {
+    let spec = XvmCallSpec {
+        context: context_from(VmId::Wasm),
+        vm_id: VmId::Evm,
+        target: target.encode(),
+        input: vec![1, 2, 3],
+        value: 0,
+        storage_deposit_limit: None,
+    };
+    let tx = CheckedEthereumTx {
+        gas_limit: U256::from(246000),
+        target,
+        value: U256::zero(),
+        input: EthereumTxInput::try_from(vec![1, 2, 3]).expect("input too large"),
+        maybe_access_list: None,
+    };
+    (spec, tx)
+}
+
+#[test]
+fn call_conditional_executes_on_success_branch() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let (primary, _) = conditional_evm_call(H160::repeat_byte(0xFF));
+        let (on_success, on_success_tx) = conditional_evm_call(H160::repeat_byte(0xEE));
+        let (on_failure, _) = conditional_evm_call(H160::repeat_byte(0xDD));
+
+        let (primary_result, follow_up_result) =
+            Xvm::call_conditional(ALICE, primary, Some(on_success), Some(on_failure));
+        let follow_up_result = follow_up_result.expect("on success branch executed");
+        MockEthereumTransact::assert_transacted(Xvm::mapped_evm_address(ALICE), on_success_tx);
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 2);
+
+        // The used weight of both calls.
+        let used_weight =
+            primary_result.unwrap().used_weight + follow_up_result.unwrap().used_weight;
+        assert_eq!(used_weight, evm_overheads * 2);
+    });
+}
+
+#[test]
+fn call_conditional_executes_on_failure_branch() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let (mut primary, _) = conditional_evm_call(H160::repeat_byte(0xFF));
+        primary.target = vec![1, 2, 3];
+        let (on_success, _) = conditional_evm_call(H160::repeat_byte(0xEE));
+        let (on_failure, on_failure_tx) = conditional_evm_call(H160::repeat_byte(0xDD));
+
+        let (primary_result, follow_up_result) =
+            Xvm::call_conditional(ALICE, primary, Some(on_success), Some(on_failure));
+        assert_eq!(
+            primary_result,
+            Err(CallFailure::revert(InvalidTarget, evm_overheads))
+        );
+        assert_ok!(follow_up_result.expect("on failure branch executed"));
+        MockEthereumTransact::assert_transacted(Xvm::mapped_evm_address(ALICE), on_failure_tx);
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 1);
+
+        // No follow-up call for the result.
+        let (mut primary, _) = conditional_evm_call(H160::repeat_byte(0xFF));
+        primary.target = vec![1, 2, 3];
+        let (on_success, _) = conditional_evm_call(H160::repeat_byte(0xEE));
+        let (_, follow_up_result) = Xvm::call_conditional(ALICE, primary, Some(on_success), None);
+        assert_eq!(follow_up_result, None);
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 1);
+    });
+}
+
+#[test]
+fn mapped_evm_address_falls_back_without_primary_entry() {
+    ExtBuilder::default().build().execute_with(|| {
+        // `BOB` has an entry in the primary mapping.
+        assert_eq!(Xvm::mapped_evm_address(BOB), BOB_EVM_ADDRESS);
+        assert_ne!(BOB_EVM_ADDRESS, HashedAccountMapping::into_h160(BOB));
+        // `ALICE` has none, mapped with the fallback.
+        assert_eq!(
+            Xvm::mapped_evm_address(ALICE),
+            HashedAccountMapping::into_h160(ALICE)
+        );
+
+        let context = context_from(VmId::Wasm);
+        let input = vec![1, 2, 3];
+        assert_ok!(Xvm::call(
+            context,
+            VmId::Evm,
+            BOB,
+            H160::repeat_byte(0xFF).encode(),
+            input.clone(),
+            0,
+            None
+        ));
+        MockEthereumTransact::assert_transacted(
+            BOB_EVM_ADDRESS,
+            CheckedEthereumTx {
+                gas_limit: U256::from(246000),
+                target: H160::repeat_byte(0xFF),
+                value: U256::zero(),
+                input: EthereumTxInput::try_from(input).expect("input too large"),
+                maybe_access_list: None,
+            },
+        );
+    });
+}
+
+#[test]
+fn call_weight_stats_are_updated_on_execution() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let evm_target = H160::repeat_byte(0xFF).encode();
+        assert_eq!(
+            Xvm::call_weight_stats(VmId::Evm),
+            CallWeightStats::default()
+        );
+        assert_eq!(
+            Xvm::call_weight_stats(VmId::Evm).average_weight(),
+            Weight::zero()
+        );
+
+        let success_weight = Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            evm_target.clone(),
+            vec![1, 2, 3],
+            0,
+            None,
+        )
+        .expect("call must succeed")
+        .used_weight;
+
+        MockEthereumTransact::set_error(Some(DispatchErrorWithPostInfo {
+            post_info: PostDispatchInfo {
+                actual_weight: Some(Weight::from_parts(300_000, 100)),
+                pays_fee: Default::default(),
+            },
+            error: DispatchError::Other("Failed to validate Ethereum tx"),
+        }));
+        let failure_weight = Xvm::call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            evm_target,
+            vec![1, 2, 3],
+            0,
+            None,
+        )
+        .expect_err("call must fail")
+        .used_weight;
+
+        // Calls failed before the execution are not recorded.
+        assert!(Xvm::call(context, VmId::Evm, ALICE, vec![1, 2, 3], vec![], 0, None).is_err());
+
+        let stats = Xvm::call_weight_stats(VmId::Evm);
+        assert_eq!(
+            stats,
+            CallWeightStats {
+                calls: 2,
+                total_weight: success_weight + failure_weight,
+                max_weight: success_weight.max(failure_weight),
+            }
+        );
+        assert_eq!(
+            stats.average_weight(),
+            (success_weight + failure_weight) / 2
+        );
+        assert_eq!(
+            Xvm::call_weight_stats(VmId::Wasm),
+            CallWeightStats::default()
+        );
+    });
+}
+
+#[test]
+fn on_chain_wasm_calls_enforce_determinism() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_eq!(Xvm::wasm_determinism(), Determinism::Enforced);
+
+        // A relaxed setting is ignored outside of simulation.
+        RELAXED_DETERMINISM.with(|relaxed| relaxed.replace(true));
+        assert_eq!(Xvm::wasm_determinism(), Determinism::Enforced);
+
+        VM_PATH.with(|path| path.replace(Some(vec![VmId::Evm])));
+        assert_eq!(Xvm::wasm_determinism(), Determinism::Relaxed);
+        VM_PATH.with(|path| path.take());
+        RELAXED_DETERMINISM.with(|relaxed| relaxed.take());
+
+        // The relaxed setting doesn't outlive the simulation.
+        let context = Context {
+            weight_limit: Weight::from_parts(1_000_000_000, 1_000_000),
+            ..context_from(VmId::Evm)
+        };
+        Xvm::simulate_call_with_determinism(
+            context,
+            VmId::Wasm,
+            ALICE,
+            BOB.encode(),
+            vec![],
+            0,
+            true,
+        );
+        assert!(!RELAXED_DETERMINISM.with(|relaxed| *relaxed.borrow()));
+        assert_eq!(Xvm::wasm_determinism(), Determinism::Enforced);
+    });
+}
+
+#[test]
+fn self_calls_are_denied_if_enabled() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let wasm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::wasm_call_overheads();
+        let denial_weight: Weight =
+            weights::SubstrateWeight::<TestRuntime>::call_denied_overheads();
+        let context = |source_vm_id: VmId| Context {
+            weight_limit: Weight::from_parts(1_000_000_000, 1_000_000),
+            ..context_from(source_vm_id)
+        };
+        let evm_self_call = || {
+            Xvm::call(
+                context(VmId::Wasm),
+                VmId::Evm,
+                ALICE,
+                Xvm::mapped_evm_address(ALICE).encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+            )
+        };
+        let wasm_self_call = || {
+            Xvm::call(
+                context(VmId::Evm),
+                VmId::Wasm,
+                ALICE,
+                ALICE.encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+            )
+        };
+
+        // Allowed by default.
+        assert_ok!(evm_self_call());
+        assert_ne!(
+            wasm_self_call().map_err(|failure| failure.reason),
+            Err(FailureReason::Error(SelfCallDenied))
+        );
+
+        DenySelfCalls::set(true);
+        assert_err!(
+            evm_self_call(),
+            CallFailure::denied(SelfCallDenied, evm_overheads, denial_weight),
+        );
+        assert_err!(
+            wasm_self_call(),
+            CallFailure::denied(SelfCallDenied, wasm_overheads, denial_weight),
+        );
+
+        // Other targets are still allowed.
+        assert_ok!(Xvm::call(
+            context(VmId::Wasm),
+            VmId::Evm,
+            ALICE,
+            H160::repeat_byte(0xFF).encode(),
+            vec![1, 2, 3],
+            0,
+            None,
+        ));
+    });
+}
+
+#[test]
+fn weight_near_exhaustion_event_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let call = |weight_limit: Weight| {
+            Xvm::call(
+                Context {
+                    weight_limit,
+                    trace_id: [7; 16],
+                    ..context_from(VmId::Wasm)
+                },
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+            )
+        };
+        let near_exhaustion_events = || {
+            System::events()
+                .into_iter()
+                .filter(|record| {
+                    matches!(
+                        record.event,
+                        RuntimeEvent::Xvm(Event::WeightNearExhaustion { .. })
+                    )
+                })
+                .count()
+        };
+        let limit = Weight::from_parts(evm_overheads.ref_time(), 1_000_000);
+
+        // Disabled by default.
+        assert_ok!(call(limit));
+        assert_eq!(near_exhaustion_events(), 0);
+
+        WeightExhaustionThreshold::set(Some(Perbill::from_percent(95)));
+        assert_ok!(call(limit));
+        System::assert_last_event(RuntimeEvent::Xvm(Event::WeightNearExhaustion {
+            trace_id: [7; 16],
+            vm_id: VmId::Evm,
+            used: evm_overheads,
+            limit,
+        }));
+
+        // Far from the limit.
+        assert_ok!(call(Weight::from_parts(1_000_000, 1_000_000)));
+        assert_eq!(near_exhaustion_events(), 1);
+    });
+}
+
+fn noop_context() -> Context {
+    // Calls are executed by the no-op VM in place of the EVM.
+    NOOP_VM.with(|noop_vm| noop_vm.replace(true));
+    Context {
+        trace_id: [3; 16],
+        ..context_from(VmId::Wasm)
+    }
+}
+
+#[test]
+fn noop_vm_call_echoes_input() {
+    ExtBuilder::default().build().execute_with(|| {
+        let target = H160::repeat_byte(0xFF).encode();
+        let output = Xvm::call(
+            noop_context(),
+            VmId::Evm,
+            ALICE,
+            target.clone(),
+            vec![1, 2, 3],
+            0,
+            None,
+        )
+        .expect("noop call must succeed");
+        assert_eq!(output.output, vec![1, 2, 3]);
+        assert_eq!(
+            output.used_weight,
+            weights::SubstrateWeight::<TestRuntime>::evm_call_overheads()
+        );
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 0);
+
+        let context = Context {
+            discard_output: true,
+            ..noop_context()
+        };
+        let output = Xvm::call(context, VmId::Evm, ALICE, target, vec![1, 2, 3], 0, None)
+            .expect("noop call must succeed");
+        assert!(output.output.is_empty());
+    });
+}
+
+#[test]
+fn noop_vm_call_denies_reentrance() {
+    ExtBuilder::default().build().execute_with(|| {
+        let target = H160::repeat_byte(0xFF).encode();
+        IN_XVM.with(|in_xvm| in_xvm.replace(true));
+        let result = Xvm::call(noop_context(), VmId::Evm, ALICE, target, vec![1], 0, None);
+        IN_XVM.with(|in_xvm| in_xvm.take());
+
+        assert_eq!(
+            result,
+            Err(CallFailure::denied(
+                ReentranceDenied,
+                weights::SubstrateWeight::<TestRuntime>::evm_call_overheads(),
+                weights::SubstrateWeight::<TestRuntime>::call_denied_overheads()
+            ))
+        );
+    });
+}
+
+#[test]
+fn noop_vm_batch_emits_summary_event() {
+    ExtBuilder::default().build().execute_with(|| {
+        let call = BatchCall {
+            vm_id: VmId::Evm,
+            target: H160::repeat_byte(0xFF).encode(),
+            input: vec![1, 2, 3],
+            value: 0,
+            storage_deposit_limit: None,
+        };
+        let output = Xvm::call_batch(noop_context(), ALICE, vec![call.clone(), call]);
+        assert!(output.results.iter().all(|(_, result)| result.is_ok()));
+
+        System::assert_last_event(RuntimeEvent::Xvm(Event::XvmBatchExecuted {
+            trace_id: [3; 16],
+            count: 2,
+            successes: 2,
+            failures: 0,
+            total_weight: weights::SubstrateWeight::<TestRuntime>::batch_call_overheads()
+                + weights::SubstrateWeight::<TestRuntime>::evm_call_overheads() * 2,
+        }));
+    });
+}
+
+#[test]
+fn deferred_calls_cannot_be_scheduled_beyond_max_depth() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        assert_ok!(Xvm::defer_call(deferred_evm_call(1)));
+
+        // The deferred call schedules another one, one level deeper.
+        MockEthereumTransact::set_defer_on_transact(Some(deferred_evm_call(2)));
+        assert_eq!(Xvm::on_idle(1, evm_overheads), evm_overheads);
+        assert_eq!(
+            DeferredCalls::<TestRuntime>::get().into_inner(),
+            vec![DeferredXvmCall {
+                depth: 1,
+                ..deferred_evm_call(2)
+            }]
+        );
+
+        // Which can't schedule any deeper call.
+        assert_eq!(Xvm::on_idle(2, evm_overheads), evm_overheads);
+        assert!(DeferredCalls::<TestRuntime>::get().is_empty());
+        System::assert_has_event(RuntimeEvent::Xvm(Event::DeferredScheduleRejected {
+            trace_id: [0; 16],
+            vm_id: VmId::Evm,
+            depth: 2,
+        }));
+        MockEthereumTransact::set_defer_on_transact(None);
+
+        // Calls deferred outside deferred calls start from zero.
+        assert_ok!(Xvm::defer_call(deferred_evm_call(3)));
+        assert_eq!(
+            DeferredCalls::<TestRuntime>::get().into_inner(),
+            vec![deferred_evm_call(3)]
+        );
+    });
+}
+
+#[test]
+fn expired_deferred_calls_are_dropped() {
+    ExtBuilder::default().build().execute_with(|| {
+        let evm_overheads: Weight = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let expired_call = DeferredXvmCall {
+            deadline: Some(1),
+            ..deferred_evm_call(1)
+        };
+        let call = DeferredXvmCall {
+            deadline: Some(2),
+            ..deferred_evm_call(2)
+        };
+        assert_ok!(Xvm::defer_call(expired_call));
+        assert_ok!(Xvm::defer_call(call));
+
+        System::set_block_number(2);
+        assert_eq!(
+            Xvm::on_idle(2, Weight::from_parts(1_000_000, 1_000_000)),
+            evm_overheads
+        );
+        assert!(DeferredCalls::<TestRuntime>::get().is_empty());
+
+        // Only the call within its deadline is executed.
+        assert_eq!(EvmCallCount::<TestRuntime>::get(), 1);
+        let events: Vec<_> = System::events().into_iter().map(|r| r.event).collect();
+        assert_eq!(
+            events,
+            vec![
+                RuntimeEvent::Xvm(Event::DeferredCallExpired {
+                    trace_id: [0; 16],
+                    vm_id: VmId::Evm,
+                    deadline: 1,
+                }),
+                RuntimeEvent::Xvm(Event::XvmCallExecuted {
+                    trace_id: [0; 16],
+                    vm_id: VmId::Evm,
+                    success: true,
+                    used_weight: evm_overheads,
+                }),
+            ]
+        );
+    });
+}
+
+#[test]
+fn supported_vms_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_eq!(Xvm::supported_vms(), vec![VmId::Evm, VmId::Wasm]);
+    });
+}
+
+#[test]
+fn pallet_account_id_is_stable() {
+    ExtBuilder::default().build().execute_with(|| {
+        let mut account = [0u8; 32];
+        account[..12].copy_from_slice(b"modlmokxvmac");
+        assert_eq!(Xvm::account_id(), AccountId::new(account));
+    });
+}
+
+#[test]
+fn weight_diagnostic_works() {
+    ExtBuilder::default().build().execute_with(|| {
+        let used_weight = Weight::from_parts(900, 10);
+        let weight_limit = Weight::from_parts(1_000, 20);
+        assert_eq!(
+            Xvm::weight_diagnostic(VmId::Evm, used_weight, weight_limit),
+            b"used 900/1000 ref time, 10/20 proof size, 900/1000 gas".to_vec()
+        );
+        assert_eq!(
+            Xvm::weight_diagnostic(VmId::Wasm, used_weight, weight_limit),
+            b"used 900/1000 ref time, 10/20 proof size".to_vec()
+        );
+    });
+}
+
+#[test]
+fn simulate_call_returns_weight_diagnostic_if_out_of_gas() {
+    ExtBuilder::default().build().execute_with(|| {
+        let weight_limit = Weight::from_parts(1_000_000, 1_000_000);
+        let context = Context {
+            weight_limit,
+            ..context_from(VmId::Wasm)
+        };
+        let evm_overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let simulate = || {
+            Xvm::simulate_call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![1, 2, 3],
+                0,
+            )
+        };
+
+        // Not set on success.
+        assert_eq!(simulate().weight_diagnostic, None);
+
+        MockEthereumTransact::set_exit_reason(ExitReason::Error(ExitError::OutOfGas));
+        let result = simulate();
+        assert_eq!(result.failure, Some(FailureReason::Error(OutOfGas)));
+        assert_eq!(
+            result.weight_diagnostic,
+            Some(Xvm::weight_diagnostic(
+                VmId::Evm,
+                evm_overheads,
+                weight_limit
+            ))
+        );
+    });
+}
+
+#[test]
+fn evm_call_maps_exit_reasons() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let evm_overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let call = |exit_reason: ExitReason| {
+            MockEthereumTransact::set_exit_reason(exit_reason);
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                H160::repeat_byte(0xFF).encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+            )
+        };
+        MockEthereumTransact::set_output(vec![42]);
+
+        assert_eq!(
+            call(ExitReason::Succeed(ExitSucceed::Returned)).map(|output| output.output),
+            Ok(vec![42])
+        );
+        assert_eq!(
+            call(ExitReason::Revert(ExitRevert::Reverted)),
+            Err(CallFailure::revert(VmRevert(vec![42]), evm_overheads))
+        );
+        assert_eq!(
+            call(ExitReason::Error(ExitError::OutOfGas)),
+            Err(CallFailure::error(OutOfGas, evm_overheads))
+        );
+        assert_eq!(
+            call(ExitReason::Error(ExitError::InvalidRange)),
+            Err(CallFailure::error(
+                VmError(b"EVM call error: InvalidRange".to_vec()),
+                evm_overheads
+            ))
+        );
+        assert_eq!(
+            call(ExitReason::Fatal(ExitFatal::NotSupported)),
+            Err(CallFailure::error(
+                VmError(b"EVM call error: NotSupported".to_vec()),
+                evm_overheads
+            )
+            .with_category(ExitCategory::Fatal))
+        );
+
+        let category = |exit_reason| call(exit_reason).map_err(|failure| failure.category);
+        assert_eq!(
+            category(ExitReason::Revert(ExitRevert::Reverted)),
+            Err(ExitCategory::Revert)
+        );
+        assert_eq!(
+            category(ExitReason::Error(ExitError::OutOfGas)),
+            Err(ExitCategory::Error)
+        );
+        assert_eq!(
+            category(ExitReason::Fatal(ExitFatal::NotSupported)),
+            Err(ExitCategory::Fatal)
+        );
+    });
+}
+
+#[cfg(feature = "test-utils")]
+#[test]
+fn scripted_ethereum_transact_returns_scripted_outcome() {
+    use test_utils::ScriptedEthereumTransact;
+
+    let tx = CheckedEthereumTx {
+        gas_limit: U256::from(246000),
+        target: H160::repeat_byte(0xFF),
+        value: U256::zero(),
+        input: EthereumTxInput::try_from(vec![1, 2, 3]).expect("input too large"),
+        maybe_access_list: None,
+    };
+    let transact = || {
+        ScriptedEthereumTransact::xvm_transact(H160::repeat_byte(0xAA), tx.clone())
+            .map(|(_, call_info)| (call_info.exit_reason, call_info.value))
+    };
+
+    assert_eq!(
+        transact(),
+        Ok((ExitReason::Succeed(ExitSucceed::Returned), vec![]))
+    );
+    for exit_reason in [
+        ExitReason::Succeed(ExitSucceed::Stopped),
+        ExitReason::Revert(ExitRevert::Reverted),
+        ExitReason::Error(ExitError::OutOfGas),
+        ExitReason::Fatal(ExitFatal::NotSupported),
+    ] {
+        ScriptedEthereumTransact::script(exit_reason.clone(), vec![42]);
+        assert_eq!(transact(), Ok((exit_reason, vec![42])));
+    }
+}
+
+#[test]
+fn evm_call_fails_if_target_not_allowed() {
+    ExtBuilder::default().build().execute_with(|| {
+        let context = context_from(VmId::Wasm);
+        let evm_overheads = weights::SubstrateWeight::<TestRuntime>::evm_call_overheads();
+        let call = |target: H160| {
+            Xvm::call(
+                context.clone(),
+                VmId::Evm,
+                ALICE,
+                target.encode(),
+                vec![1, 2, 3],
+                0,
+                None,
+            )
+        };
+
+        assert_noop!(
+            call(BLOCKED_EVM_TARGET),
+            CallFailure::revert(TargetNotAllowed, evm_overheads),
+        );
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), None);
+
+        assert_ok!(call(H160::repeat_byte(0xFF)));
+        assert_eq!(MockEthereumTransact::in_xvm_on_transact(), Some(true));
+    });
+}
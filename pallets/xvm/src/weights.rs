@@ -16,14 +16,20 @@
 // You should have received a copy of the GNU General Public License
 // along with Astar. If not, see <http://www.gnu.org/licenses/>.
 
-//! Autogenerated weights for pallet_xvm
+//! Weights for pallet_xvm, partly hand-edited since the last benchmark run (see the note below)
 //!
-//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
+//! LAST GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 4.0.0-dev
 //! DATE: 2023-08-04, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
 //! WORST CASE MAP SIZE: `1000000`
 //! HOSTNAME: `devserver-01`, CPU: `Intel(R) Xeon(R) E-2236 CPU @ 3.40GHz`
 //! EXECUTION: Some(Wasm), WASM-EXECUTION: Compiled, CHAIN: Some("shibuya-dev"), DB CACHE: 1024
 
+// NOTE: the storage reads and proof sizes of the call overheads, and the `call_denied_overheads`,
+// `batch_call_overheads`, `set_paused`, `stage_input` and `commit_and_call` weights, were added
+// by hand after the last benchmark run. Their execution times are not measured yet, regenerate
+// this file with the command below before relying on them, every weight has a benchmark in
+// `benchmarking.rs`.
+
 // Executed Command:
 // ./target/release/astar-collator
 // benchmark
@@ -43,6 +49,7 @@
 #![allow(unused_parens)]
 #![allow(unused_imports)]
 
+use astar_primitives::xvm::VmId;
 use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
 use core::marker::PhantomData;
 
@@ -50,41 +57,188 @@ use core::marker::PhantomData;
 pub trait WeightInfo {
 	fn evm_call_overheads() -> Weight;
 	fn wasm_call_overheads() -> Weight;
+	fn call_denied_overheads() -> Weight;
+	fn batch_call_overheads() -> Weight;
+	fn set_paused() -> Weight;
+	fn stage_input(n: u32, ) -> Weight;
+	fn commit_and_call() -> Weight;
 }
 
 /// Weights for pallet_xvm using the Substrate node and recommended hardware.
 pub struct SubstrateWeight<T>(PhantomData<T>);
 impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	// Storage: Xvm CallsInBlock (r:1 w:0)
+	// Proof: Xvm CallsInBlock (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: UnifiedAccounts EvmToNative (r:2 w:0)
+	// Proof: UnifiedAccounts EvmToNative (max_values: None, max_size: Some(68), added: 2543, mode: MaxEncodedLen)
+	// Storage: System Account (r:2 w:0)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	// Storage: UnifiedAccounts NativeToEvm (r:1 w:0)
+	// Proof: UnifiedAccounts NativeToEvm (max_values: None, max_size: Some(68), added: 2543, mode: MaxEncodedLen)
 	fn evm_call_overheads() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 730_000 picoseconds.
-		Weight::from_parts(754_000, 0)
+		//  Estimated: `14820`
+		Weight::from_parts(754_000, 14820)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
 	}
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	// Storage: Xvm CallsInBlock (r:1 w:0)
+	// Proof: Xvm CallsInBlock (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: System Account (r:2 w:0)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
 	fn wasm_call_overheads() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 309_000 picoseconds.
-		Weight::from_parts(347_000, 0)
+		//  Estimated: `7191`
+		Weight::from_parts(347_000, 7191)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+	}
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	// Storage: Xvm CallsInBlock (r:1 w:0)
+	// Proof: Xvm CallsInBlock (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	fn call_denied_overheads() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `1985`
+		Weight::from_parts(128_000, 1985)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+	}
+	fn batch_call_overheads() -> Weight {
+		Weight::from_parts(231_000, 0)
+	}
+	// Storage: Xvm XvmPaused (r:0 w:1)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	fn set_paused() -> Weight {
+		Weight::from_parts(6_382_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	// Storage: System Number (r:1 w:0)
+	// Proof: System Number (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: Xvm StagedInputs (r:1 w:1)
+	// Proof: Xvm StagedInputs (max_values: None, max_size: Some(262_219), added: 264_694, mode: MaxEncodedLen)
+	// Storage: System Account (r:1 w:1)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// The range of component `n` is `[0, 262144]`.
+	fn stage_input(n: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `268_287`
+		Weight::from_parts(19_836_642, 268_287)
+			.saturating_add(Weight::from_parts(668, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	// Storage: System Number (r:1 w:0)
+	// Proof: System Number (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: Xvm StagedInputs (r:1 w:1)
+	// Proof: Xvm StagedInputs (max_values: None, max_size: Some(262_219), added: 264_694, mode: MaxEncodedLen)
+	// Storage: System Account (r:1 w:1)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	fn commit_and_call() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `269_773`
+		Weight::from_parts(21_452_000, 269_773)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
 	}
 }
 
 // For backwards compatibility and tests
 impl WeightInfo for () {
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	// Storage: Xvm CallsInBlock (r:1 w:0)
+	// Proof: Xvm CallsInBlock (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: UnifiedAccounts EvmToNative (r:2 w:0)
+	// Proof: UnifiedAccounts EvmToNative (max_values: None, max_size: Some(68), added: 2543, mode: MaxEncodedLen)
+	// Storage: System Account (r:2 w:0)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	// Storage: UnifiedAccounts NativeToEvm (r:1 w:0)
+	// Proof: UnifiedAccounts NativeToEvm (max_values: None, max_size: Some(68), added: 2543, mode: MaxEncodedLen)
 	fn evm_call_overheads() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 730_000 picoseconds.
-		Weight::from_parts(754_000, 0)
+		//  Estimated: `14820`
+		Weight::from_parts(754_000, 14820)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
 	}
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	// Storage: Xvm CallsInBlock (r:1 w:0)
+	// Proof: Xvm CallsInBlock (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: System Account (r:2 w:0)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
 	fn wasm_call_overheads() -> Weight {
 		// Proof Size summary in bytes:
-		//  Measured:  `0`
-		//  Estimated: `0`
-		// Minimum execution time: 309_000 picoseconds.
-		Weight::from_parts(347_000, 0)
+		//  Estimated: `7191`
+		Weight::from_parts(347_000, 7191)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+	}
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	// Storage: Xvm CallsInBlock (r:1 w:0)
+	// Proof: Xvm CallsInBlock (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	fn call_denied_overheads() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `1985`
+		Weight::from_parts(128_000, 1985)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
 	}
+	fn batch_call_overheads() -> Weight {
+		Weight::from_parts(231_000, 0)
+	}
+	// Storage: Xvm XvmPaused (r:0 w:1)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	fn set_paused() -> Weight {
+		Weight::from_parts(6_382_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	// Storage: System Number (r:1 w:0)
+	// Proof: System Number (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: Xvm StagedInputs (r:1 w:1)
+	// Proof: Xvm StagedInputs (max_values: None, max_size: Some(262_219), added: 264_694, mode: MaxEncodedLen)
+	// Storage: System Account (r:1 w:1)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	/// The range of component `n` is `[0, 262144]`.
+	fn stage_input(n: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `268_287`
+		Weight::from_parts(19_836_642, 268_287)
+			.saturating_add(Weight::from_parts(668, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	// Storage: System Number (r:1 w:0)
+	// Proof: System Number (max_values: Some(1), max_size: Some(4), added: 499, mode: MaxEncodedLen)
+	// Storage: Xvm StagedInputs (r:1 w:1)
+	// Proof: Xvm StagedInputs (max_values: None, max_size: Some(262_219), added: 264_694, mode: MaxEncodedLen)
+	// Storage: System Account (r:1 w:1)
+	// Proof: System Account (max_values: None, max_size: Some(128), added: 2603, mode: MaxEncodedLen)
+	// Storage: Xvm XvmPaused (r:1 w:0)
+	// Proof: Xvm XvmPaused (max_values: Some(1), max_size: Some(1), added: 496, mode: MaxEncodedLen)
+	fn commit_and_call() -> Weight {
+		// Proof Size summary in bytes:
+		//  Estimated: `269_773`
+		Weight::from_parts(21_452_000, 269_773)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+}
+
+/// The only mapping of VMs to their call overheads, a new VM must be added here.
+pub fn call_overheads<W: WeightInfo>(vm_id: VmId) -> Weight {
+	match vm_id {
+		VmId::Evm => W::evm_call_overheads(),
+		VmId::Wasm => W::wasm_call_overheads(),
+	}
+}
+
+/// Estimated call overheads of `vm_id` with the default `()` weights, for off-chain tooling
+/// without a runtime at hand.
+///
+/// This is only an estimate, the runtime-specific weights may differ.
+pub fn estimated_call_overheads(vm_id: VmId) -> Weight {
+	call_overheads::<()>(vm_id)
 }
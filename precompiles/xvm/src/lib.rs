@@ -22,7 +22,7 @@ extern crate alloc;
 use alloc::format;
 
 use astar_primitives::{
-    xvm::{Context, FailureReason, VmId, XvmCall},
+    xvm::{ContextBuilder, FailureReason, VmId, WeightBreakdown, XvmCall},
     Balance,
 };
 use fp_evm::{PrecompileHandle, PrecompileOutput};
@@ -94,10 +94,10 @@ where
             gas_limit = gas_limit.min(user_limit);
         }
         let weight_limit = R::GasWeightMapping::gas_to_weight(gas_limit, true);
-        let xvm_context = Context {
-            source_vm_id: VmId::Evm,
-            weight_limit,
-        };
+        let xvm_context = ContextBuilder::new(VmId::Evm)
+            .weight_limit(weight_limit)
+            .build()
+            .map_err(|_| revert("zero weight limit"))?;
 
         let call_to = input.read::<Bytes>()?.0;
         let call_input = input.read::<Bytes>()?.0;
@@ -122,10 +122,13 @@ where
             storage_deposit_limit,
         );
 
-        let used_weight = match &call_result {
-            Ok(s) => s.used_weight,
-            Err(f) => f.used_weight,
-        };
+        // Only the used weight is recorded, the unused weight of the gas limit is refunded.
+        let breakdown = WeightBreakdown::new(weight_limit, XC::call_overheads(vm_id), &call_result);
+        log::trace!(
+            target: "xvm-precompile::xvm_call",
+            "weight: {:?}", breakdown
+        );
+        let used_weight = breakdown.total_used;
         handle.record_cost(R::GasWeightMapping::weight_to_gas(used_weight))?;
         handle
             .record_external_cost(Some(used_weight.ref_time()), Some(used_weight.proof_size()))?;
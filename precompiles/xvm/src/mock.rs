@@ -261,6 +261,27 @@ impl WeightLimitCalledWith {
 struct MockXvmWithArgsCheck;
 impl XvmCall<AccountId> for MockXvmWithArgsCheck {
     fn call(
+        context: Context,
+        vm_id: VmId,
+        source: AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        storage_deposit_limit: Option<Balance>,
+    ) -> CallResult {
+        Self::call_with_dry_run(
+            context,
+            vm_id,
+            source,
+            target,
+            input,
+            value,
+            storage_deposit_limit,
+            false,
+        )
+    }
+
+    fn call_with_dry_run(
         context: Context,
         vm_id: VmId,
         _source: AccountId,
@@ -268,6 +289,7 @@ impl XvmCall<AccountId> for MockXvmWithArgsCheck {
         input: Vec<u8>,
         _value: Balance,
         _storage_deposit_limit: Option<Balance>,
+        dry_run: bool,
     ) -> CallResult {
         ensure!(
             vm_id != VmId::Evm,
@@ -281,6 +303,9 @@ impl XvmCall<AccountId> for MockXvmWithArgsCheck {
             input.len() <= 1024,
             CallFailure::revert(InputTooLarge, Weight::zero()),
         );
+        if dry_run {
+            return Ok(CallOutput::new(vec![], Weight::zero()));
+        }
 
         WeightLimitCalledWith::set(context.weight_limit);
 
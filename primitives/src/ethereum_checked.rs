@@ -98,6 +98,13 @@ pub trait CheckedEthereumTransact {
         source: H160,
         checked_tx: CheckedEthereumTx,
     ) -> Result<(PostDispatchInfo, CallInfo), DispatchErrorWithPostInfo>;
+
+    /// Current EVM nonce of the `source` account, which is the nonce the next
+    /// transaction from `source` will be executed with.
+    fn account_nonce(source: H160) -> U256;
+
+    /// Current EVM balance of the `address` account.
+    fn account_balance(address: H160) -> U256;
 }
 
 /// Mapping from `Account` to `H160`.
@@ -105,6 +112,19 @@ pub trait AccountMapping<AccountId> {
     fn into_h160(account: AccountId) -> H160;
 }
 
+/// Mapping from `Account` to `H160`, for the accounts that have an entry.
+pub trait TryAccountMapping<AccountId> {
+    /// The mapped `H160` of `account`, `None` if it has no entry.
+    fn try_into_h160(account: &AccountId) -> Option<H160>;
+}
+
+/// No account has an entry.
+impl<AccountId> TryAccountMapping<AccountId> for () {
+    fn try_into_h160(_account: &AccountId) -> Option<H160> {
+        None
+    }
+}
+
 /// Hashed derive mapping for converting account id to evm address
 pub struct HashedAccountMapping<H>(sp_std::marker::PhantomData<H>);
 impl<H: Hasher<Out = H256>> AccountMapping<AccountId> for HashedAccountMapping<H> {
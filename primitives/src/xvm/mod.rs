@@ -0,0 +1,1082 @@
+// This file is part of Astar.
+
+// Copyright (C) 2019-2023 Stake Technologies Pte.Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Astar is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Astar is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Astar. If not, see <http://www.gnu.org/licenses/>.
+
+use crate::Balance;
+
+use fp_evm::{ExitReason, ExitSucceed};
+use frame_support::{
+    dispatch::{DispatchErrorWithPostInfo, DispatchResultWithPostInfo, PostDispatchInfo},
+    weights::Weight,
+};
+use parity_scale_codec::{Decode, DecodeAll, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_core::{H160, H256, U256};
+use sp_runtime::{DispatchError, RuntimeDebug};
+use sp_std::{convert::TryFrom, fmt, prelude::*, result::Result, str::FromStr};
+
+#[cfg(test)]
+mod tests;
+
+/// Vm Id.
+#[repr(u8)]
+#[derive(PartialEq, Eq, Copy, Clone, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub enum VmId {
+    Evm = 0x0F,
+    Wasm = 0x1F,
+}
+
+impl VmId {
+    /// Stable numeric discriminant for external protocols, such as FFI or cross-chain
+    /// messages, independent of the `repr(u8)` value and the SCALE encoding.
+    ///
+    /// The assignments never change: `Evm` is `0` and `Wasm` is `1`.
+    pub const fn as_u8(self) -> u8 {
+        match self {
+            VmId::Evm => 0,
+            VmId::Wasm => 1,
+        }
+    }
+
+    /// The VM of the stable numeric discriminant `n`, see `as_u8`, or `None` if unknown.
+    pub const fn from_u8(n: u8) -> Option<Self> {
+        match n {
+            0 => Some(VmId::Evm),
+            1 => Some(VmId::Wasm),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<u8> for VmId {
+    type Error = FailureReason;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value == VmId::Evm as u8 {
+            Ok(VmId::Evm)
+        } else if value == VmId::Wasm as u8 {
+            Ok(VmId::Wasm)
+        } else {
+            Err(FailureReason::Error(FailureError::InvalidVmId))
+        }
+    }
+}
+
+impl FromStr for VmId {
+    type Err = FailureReason;
+
+    /// Parse the human-readable VM name, case-insensitive.
+    ///
+    /// Fails with `InvalidVmId` for unknown VMs.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("evm") {
+            Ok(VmId::Evm)
+        } else if s.eq_ignore_ascii_case("wasm") {
+            Ok(VmId::Wasm)
+        } else {
+            Err(FailureReason::Error(FailureError::InvalidVmId))
+        }
+    }
+}
+
+impl fmt::Display for VmId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmId::Evm => f.write_str("evm"),
+            VmId::Wasm => f.write_str("wasm"),
+        }
+    }
+}
+
+/// Decoded target of a XVM call.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, MaxEncodedLen, RuntimeDebug, TypeInfo)]
+pub enum XvmTarget<AccountId> {
+    /// EVM contract address.
+    Evm(H160),
+    /// WASM contract account.
+    Wasm(AccountId),
+}
+
+impl<AccountId: Decode + MaxEncodedLen> TryFrom<(VmId, Vec<u8>)> for XvmTarget<AccountId> {
+    type Error = FailureRevert;
+
+    /// Decode `target` for `vm_id`.
+    ///
+    /// Fails with `TargetVmMismatch` if `target` has the length of the other VM's targets,
+    /// `EmptyTarget` if an EVM `target` is empty, or `InvalidTarget` otherwise.
+    fn try_from((vm_id, target): (VmId, Vec<u8>)) -> Result<Self, Self::Error> {
+        let evm_target_len = H160::len_bytes();
+        let wasm_target_len = AccountId::max_encoded_len();
+        match vm_id {
+            VmId::Evm if target.len() == evm_target_len => {
+                Ok(XvmTarget::Evm(H160::from_slice(&target)))
+            }
+            VmId::Evm if target.len() == wasm_target_len => Err(FailureRevert::TargetVmMismatch),
+            VmId::Evm if target.is_empty() => Err(FailureRevert::EmptyTarget),
+            VmId::Evm => Err(FailureRevert::InvalidTarget),
+            VmId::Wasm if target.len() == evm_target_len && wasm_target_len != evm_target_len => {
+                Err(FailureRevert::TargetVmMismatch)
+            }
+            VmId::Wasm => AccountId::decode(&mut target.as_ref())
+                .map(XvmTarget::Wasm)
+                .map_err(|_| FailureRevert::InvalidTarget),
+        }
+    }
+}
+
+/// XVM call info on success.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct CallOutput {
+    /// Output of the call.
+    pub output: Vec<u8>,
+    /// Actual used weight.
+    pub used_weight: Weight,
+    /// Balance of the EVM target after the call, only set for EVM calls with value.
+    pub target_balance_after: Option<U256>,
+    /// Gas used by the EVM execution after refunds, only set for EVM calls.
+    pub used_gas: Option<U256>,
+    /// Storage deposit charged by the WASM execution, only set for WASM calls. `0` if the
+    /// call released storage and the deposit was refunded.
+    pub storage_deposit: Option<Balance>,
+    /// Whether `output` is truncated to the max output size.
+    pub truncated: bool,
+    /// Output length before truncation.
+    pub original_len: u32,
+    /// Normalized success status of the target VM, `0` by default. See `call_status` for
+    /// the codes.
+    pub status: u32,
+}
+
+/// Normalized success status codes of `CallOutput::status`.
+pub mod call_status {
+    /// Returned normally, the status of WASM calls without return flags.
+    pub const RETURNED: u32 = 0;
+    /// EVM execution stopped, by `STOP` or reaching the end of the code.
+    pub const STOPPED: u32 = 1;
+    /// EVM execution self-destructed the contract.
+    pub const SUICIDED: u32 = 2;
+}
+
+/// Normalized status of an EVM success exit reason.
+pub fn evm_call_status(reason: ExitSucceed) -> u32 {
+    match reason {
+        ExitSucceed::Returned => call_status::RETURNED,
+        ExitSucceed::Stopped => call_status::STOPPED,
+        ExitSucceed::Suicided => call_status::SUICIDED,
+    }
+}
+
+impl CallOutput {
+    /// Create a new `CallOutput`.
+    pub fn new(output: Vec<u8>, used_weight: Weight) -> Self {
+        Self {
+            original_len: output.len() as u32,
+            output,
+            used_weight,
+            target_balance_after: None,
+            used_gas: None,
+            storage_deposit: None,
+            truncated: false,
+            status: call_status::RETURNED,
+        }
+    }
+
+    /// Set the normalized success status.
+    pub fn with_status(mut self, status: u32) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Set the gas used by the EVM execution.
+    pub fn with_used_gas(mut self, used_gas: U256) -> Self {
+        self.used_gas = Some(used_gas);
+        self
+    }
+
+    /// Set the storage deposit charged by the WASM execution.
+    pub fn with_storage_deposit(mut self, storage_deposit: Balance) -> Self {
+        self.storage_deposit = Some(storage_deposit);
+        self
+    }
+
+    /// Validate the output with `is_valid`, fails with `OutputDecodeFailed` if not valid.
+    pub fn validated(self, is_valid: impl FnOnce(&[u8]) -> bool) -> CallResult {
+        if is_valid(&self.output) {
+            Ok(self)
+        } else {
+            Err(CallFailure::revert(
+                FailureRevert::OutputDecodeFailed,
+                self.used_weight,
+            ))
+        }
+    }
+}
+
+/// Output validator which checks the output can be fully SCALE decoded as `T`.
+pub fn scale_decodable<T: Decode>(output: &[u8]) -> bool {
+    T::decode_all(&mut &output[..]).is_ok()
+}
+
+/// Output validator which checks the output is ABI encoded with `words` static 32-byte words.
+pub fn abi_decodable(words: usize) -> impl Fn(&[u8]) -> bool {
+    move |output| Some(output.len()) == words.checked_mul(32)
+}
+
+/// XVM call failure.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct CallFailure {
+    /// Failure reason.
+    pub reason: FailureReason,
+    /// Actual used weight.
+    pub used_weight: Weight,
+    /// Weight refunded against the full call overheads, if the call was denied early.
+    pub refunded_weight: Weight,
+    /// Broad category of the failure, `Revert` or `Error` following `reason`, or `Fatal` for
+    /// EVM fatal errors.
+    pub category: ExitCategory,
+}
+
+/// Broad category of a VM exit, the four EVM `ExitReason` cases, to branch on failures
+/// without matching their reasons.
+///
+/// WASM calls reverted with the `REVERT` flag are `Revert`, other WASM failures are `Error`.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum ExitCategory {
+    /// The execution succeeded.
+    Succeed,
+    /// The execution was reverted.
+    Revert,
+    /// The execution failed with an error.
+    Error,
+    /// The execution failed with an unrecoverable error.
+    Fatal,
+}
+
+impl From<&ExitReason> for ExitCategory {
+    fn from(reason: &ExitReason) -> Self {
+        match reason {
+            ExitReason::Succeed(_) => ExitCategory::Succeed,
+            ExitReason::Revert(_) => ExitCategory::Revert,
+            ExitReason::Error(_) => ExitCategory::Error,
+            ExitReason::Fatal(_) => ExitCategory::Fatal,
+        }
+    }
+}
+
+impl CallFailure {
+    /// Create a new `CallFailure` on revert.
+    pub fn revert(details: FailureRevert, used_weight: Weight) -> Self {
+        Self {
+            reason: FailureReason::Revert(details),
+            used_weight,
+            refunded_weight: Weight::zero(),
+            category: ExitCategory::Revert,
+        }
+    }
+
+    /// Create a new `CallFailure` on error.
+    pub fn error(details: FailureError, used_weight: Weight) -> Self {
+        Self {
+            reason: FailureReason::Error(details),
+            used_weight,
+            refunded_weight: Weight::zero(),
+            category: ExitCategory::Error,
+        }
+    }
+
+    /// Create a new `CallFailure` on early denial, before the call is executed.
+    ///
+    /// Only `denial_overheads` is charged, the rest of `overheads` is refunded.
+    pub fn denied(details: FailureError, overheads: Weight, denial_overheads: Weight) -> Self {
+        Self {
+            reason: FailureReason::Error(details),
+            used_weight: denial_overheads,
+            refunded_weight: overheads.saturating_sub(denial_overheads),
+            category: ExitCategory::Error,
+        }
+    }
+
+    /// Set the exit category.
+    pub fn with_category(mut self, category: ExitCategory) -> Self {
+        self.category = category;
+        self
+    }
+}
+
+/// Failure reason of XVM calls.
+///
+/// `Error` vs `Revert`:
+/// - `Error` is for execution failed and the VM must stop. It maps to EVM
+///  `ExitError/ExistFatal` and WASM `DispatchError`.
+/// - `Revert` is for execution succeeded but the callee explicitly asked to
+///  revert. It maps to EVM `ExitRevert` and WASM `REVERT` flag. It also includes
+///  the case that wrong input was passed to XVM call, for instance invalid target,
+///  as from VM/WASM perspective, it's an input guard condition failure.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum FailureReason {
+    /// XVM call failed with explicit revert.
+    Revert(FailureRevert),
+    /// XVM call failed with error.
+    Error(FailureError),
+}
+
+/// Failure reason on revert.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum FailureRevert {
+    /// Target contract address is invalid.
+    InvalidTarget,
+    /// The EVM target is empty, contract creation isn't supported.
+    EmptyTarget,
+    /// The EVM target isn't allowed by the runtime.
+    TargetNotAllowed,
+    /// Target contract address is encoded for the other VM.
+    TargetVmMismatch,
+    /// Input is too large.
+    InputTooLarge,
+    /// EVM access list has too many entries.
+    AccessListTooLarge,
+    /// Output doesn't match the shape expected by the caller.
+    OutputDecodeFailed,
+    /// Output exceeds the max output size.
+    OutputTooLarge,
+    /// The gas limit of a raw EVM transaction exceeds the weight limit.
+    GasLimitTooHigh,
+    /// VM execution exit with revert.
+    VmRevert(Vec<u8>),
+}
+
+/// Failure reason on error.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum FailureError {
+    /// Invalid VM id.
+    InvalidVmId,
+    /// Calling the contracts in the same VM is not allowed.
+    SameVmCallDenied,
+    /// Reentrance is not allowed.
+    ReentranceDenied,
+    /// The EVM nonce of the source doesn't match the expected one.
+    NonceMismatch,
+    /// The storage deposit limit is exhausted, or the source can't pay the storage deposit.
+    OutOfStorageDeposit,
+    /// The source doesn't have enough free balance to transfer the value.
+    InsufficientBalance,
+    /// Transferring the value would leave the source below existential deposit, only checked
+    /// for WASM calls.
+    WouldReapAccount,
+    /// An explicit gas limit is required for EVM calls.
+    GasLimitRequired,
+    /// The target VM isn't enabled in the runtime.
+    VmNotSupported(VmId),
+    /// The value exceeds the max value transferable in a single call.
+    ValueTooLarge,
+    /// The VM execution ran out of gas, the call may succeed with a higher weight limit.
+    OutOfGas,
+    /// The caller isn't trusted to call from an arbitrary EVM address.
+    UntrustedCaller,
+    /// XVM calls are paused.
+    Paused,
+    /// The WASM contract trapped, unlike a revert the contract didn't return.
+    ContractTrapped,
+    /// No WASM contract at the target address.
+    ContractNotFound,
+    /// The max number of XVM calls in the block is reached.
+    CallLimitReached,
+    /// The target is the caller's own counterpart in the target VM.
+    SelfCallDenied,
+    /// An EVM `uint256` value doesn't fit in `Balance`.
+    BalanceConversionOverflow,
+    /// The max number of XVM calls to the target in the block is reached.
+    TargetRateLimited,
+    /// The value transfer would leave the target below the existential deposit.
+    BelowExistentialDeposit,
+    /// The call failed with error on EVM or WASM execution.
+    VmError(Vec<u8>),
+}
+
+/// XVM call result.
+pub type CallResult = Result<CallOutput, CallFailure>;
+
+impl CallOutput {
+    /// Post dispatch info with the used weight as the actual weight.
+    pub fn post_info(&self) -> PostDispatchInfo {
+        Some(self.used_weight).into()
+    }
+}
+
+impl CallFailure {
+    /// Convert into a dispatch error, with the used weight as the actual weight.
+    ///
+    /// `Error` failures are mapped to `on_error`, and `Revert` failures to `on_revert`.
+    pub fn into_dispatch_error(
+        self,
+        on_error: impl Into<DispatchError>,
+        on_revert: impl Into<DispatchError>,
+    ) -> DispatchErrorWithPostInfo {
+        let error = match self.reason {
+            FailureReason::Error(_) => on_error.into(),
+            FailureReason::Revert(_) => on_revert.into(),
+        };
+        DispatchErrorWithPostInfo {
+            post_info: Some(self.used_weight).into(),
+            error,
+        }
+    }
+}
+
+/// Convert a XVM call result into a dispatch result, for pallets dispatching XVM calls
+/// in extrinsics.
+///
+/// The used weight is reported as the actual weight in both cases, so the unused weight
+/// is refunded. See `CallFailure::into_dispatch_error` for the errors mapping.
+pub fn into_dispatch_result(
+    result: CallResult,
+    on_error: impl Into<DispatchError>,
+    on_revert: impl Into<DispatchError>,
+) -> DispatchResultWithPostInfo {
+    result
+        .map(|output| output.post_info())
+        .map_err(|failure| failure.into_dispatch_error(on_error, on_revert))
+}
+
+/// Compact SCALE encoding of `CallResult`, for relaying many results with less bandwidth.
+///
+/// Only the success flag, used weight, and output or failure reason are encoded. Other
+/// fields are reset to their defaults on decoding, as by `CallOutput::new` and
+/// `CallFailure::{revert, error}`.
+pub trait CompactCallResult: Sized {
+    /// Encode into the compact form.
+    fn encode_compact(&self) -> Vec<u8>;
+    /// Decode from the compact form.
+    fn decode_compact(encoded: &[u8]) -> Result<Self, parity_scale_codec::Error>;
+}
+
+/// The compact form of `CallResult`, the variant index being the success flag.
+#[derive(Encode, Decode)]
+enum CompactEncoded {
+    Failure {
+        used_weight: Weight,
+        reason: FailureReason,
+    },
+    Success {
+        used_weight: Weight,
+        output: Vec<u8>,
+    },
+}
+
+impl CompactCallResult for CallResult {
+    fn encode_compact(&self) -> Vec<u8> {
+        match self {
+            Ok(output) => CompactEncoded::Success {
+                used_weight: output.used_weight,
+                output: output.output.clone(),
+            },
+            Err(failure) => CompactEncoded::Failure {
+                used_weight: failure.used_weight,
+                reason: failure.reason.clone(),
+            },
+        }
+        .encode()
+    }
+
+    fn decode_compact(mut encoded: &[u8]) -> Result<Self, parity_scale_codec::Error> {
+        Ok(match CompactEncoded::decode_all(&mut encoded)? {
+            CompactEncoded::Success {
+                used_weight,
+                output,
+            } => Ok(CallOutput::new(output, used_weight)),
+            CompactEncoded::Failure {
+                used_weight,
+                reason,
+            } => Err(match reason {
+                FailureReason::Revert(details) => CallFailure::revert(details, used_weight),
+                FailureReason::Error(details) => CallFailure::error(details, used_weight),
+            }),
+        })
+    }
+}
+
+/// Weight breakdown of a XVM call, for refunding the unused weight.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct WeightBreakdown {
+    /// The weight limit of the call.
+    pub requested: Weight,
+    /// XVM overheads.
+    pub overheads: Weight,
+    /// Weight used by the VM execution, excluding the overheads.
+    pub execution: Weight,
+    /// Total used weight, which is `overheads + execution`.
+    pub total_used: Weight,
+}
+
+impl WeightBreakdown {
+    /// Breakdown of `result` of a call with `requested` weight limit and `overheads`.
+    ///
+    /// Calls failed before entering the VM may use less than `overheads`, in which case all
+    /// the used weight is counted as overheads.
+    pub fn new(requested: Weight, overheads: Weight, result: &CallResult) -> Self {
+        let total_used = match result {
+            Ok(output) => output.used_weight,
+            Err(failure) => failure.used_weight,
+        };
+        let overheads = overheads.min(total_used);
+        Self {
+            requested,
+            overheads,
+            execution: total_used.saturating_sub(overheads),
+            total_used,
+        }
+    }
+
+    /// The unused weight to refund.
+    pub fn unused(&self) -> Weight {
+        self.requested.saturating_sub(self.total_used)
+    }
+}
+
+/// XVM call outcome, normalized to the same shape regardless of the VM.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum XvmOutcome {
+    /// Call succeeded with output.
+    Success(Vec<u8>),
+    /// Call reverted.
+    Revert(NormalizedRevert),
+    /// Call failed with error.
+    Error(NormalizedError),
+}
+
+/// Normalized revert of XVM calls.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum NormalizedRevert {
+    /// Call was rejected by XVM before entering the VM, for instance invalid target.
+    Rejected(FailureRevert),
+    /// VM execution reverted with the encoded error data.
+    Vm(Vec<u8>),
+}
+
+/// Normalized error of XVM calls.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum NormalizedError {
+    /// Call was rejected by XVM before entering the VM, for instance reentrance.
+    Rejected(FailureError),
+    /// VM execution failed with the error message.
+    Vm(Vec<u8>),
+}
+
+impl From<CallResult> for XvmOutcome {
+    fn from(result: CallResult) -> Self {
+        match result {
+            Ok(output) => XvmOutcome::Success(output.output),
+            Err(failure) => match failure.reason {
+                FailureReason::Revert(FailureRevert::VmRevert(data)) => {
+                    XvmOutcome::Revert(NormalizedRevert::Vm(data))
+                }
+                FailureReason::Revert(revert) => {
+                    XvmOutcome::Revert(NormalizedRevert::Rejected(revert))
+                }
+                FailureReason::Error(FailureError::VmError(msg)) => {
+                    XvmOutcome::Error(NormalizedError::Vm(msg))
+                }
+                FailureReason::Error(error) => XvmOutcome::Error(NormalizedError::Rejected(error)),
+            },
+        }
+    }
+}
+
+/// The selector of EVM revert message, calculated by: `Keccak256::digest(b"Error(string)")[..4]`
+pub const EVM_ERROR_MSG_SELECTOR: [u8; 4] = [8, 195, 121, 160];
+
+/// Decode the revert reason from EVM revert data encoded as `Error(string)`.
+///
+/// Returns `None` if the data isn't an `Error(string)` revert.
+pub fn decode_evm_revert_reason(data: &[u8]) -> Option<Vec<u8>> {
+    let read_usize = |word: &[u8]| -> Option<usize> {
+        let value = U256::from_big_endian(word);
+        if value > U256::from(u32::MAX) {
+            None
+        } else {
+            Some(value.low_u32() as usize)
+        }
+    };
+
+    if data.get(..4)? != EVM_ERROR_MSG_SELECTOR {
+        return None;
+    }
+    let payload = &data[4..];
+    let offset = read_usize(payload.get(..32)?)?;
+    let len_end = offset.checked_add(32)?;
+    let len = read_usize(payload.get(offset..len_end)?)?;
+    payload
+        .get(len_end..len_end.checked_add(len)?)
+        .map(|reason| reason.to_vec())
+}
+
+/// Type of an EVM custom error parameter, only static types are supported.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum EvmAbiType {
+    Address,
+    Bool,
+    Uint256,
+    Bytes32,
+}
+
+impl EvmAbiType {
+    /// The canonical type name, as used in error signatures.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvmAbiType::Address => "address",
+            EvmAbiType::Bool => "bool",
+            EvmAbiType::Uint256 => "uint256",
+            EvmAbiType::Bytes32 => "bytes32",
+        }
+    }
+
+    /// Decode an ABI encoded 32-byte word, `None` if it isn't a valid value of the type.
+    fn decode(&self, word: &[u8]) -> Option<EvmAbiValue> {
+        match self {
+            EvmAbiType::Address => {
+                if word[..12].iter().any(|b| *b != 0) {
+                    return None;
+                }
+                Some(EvmAbiValue::Address(H160::from_slice(&word[12..])))
+            }
+            EvmAbiType::Bool => match U256::from_big_endian(word) {
+                value if value.is_zero() => Some(EvmAbiValue::Bool(false)),
+                value if value == U256::one() => Some(EvmAbiValue::Bool(true)),
+                _ => None,
+            },
+            EvmAbiType::Uint256 => Some(EvmAbiValue::Uint256(U256::from_big_endian(word))),
+            EvmAbiType::Bytes32 => Some(EvmAbiValue::Bytes32(H256::from_slice(word))),
+        }
+    }
+}
+
+/// A decoded EVM custom error parameter.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub enum EvmAbiValue {
+    Address(H160),
+    Bool(bool),
+    Uint256(U256),
+    Bytes32(H256),
+}
+
+/// Signature of a known EVM custom error.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct EvmCustomErrorSignature {
+    /// The selector, first 4 bytes of the Keccak256 hash of the error signature.
+    pub selector: [u8; 4],
+    /// The error name.
+    pub name: Vec<u8>,
+    /// The error parameter types.
+    pub params: Vec<EvmAbiType>,
+}
+
+impl EvmCustomErrorSignature {
+    /// Signature of the error `name(params)`, with the selector calculated from it.
+    pub fn new(name: &[u8], params: Vec<EvmAbiType>) -> Self {
+        let mut signature = name.to_vec();
+        signature.push(b'(');
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                signature.push(b',');
+            }
+            signature.extend_from_slice(param.name().as_bytes());
+        }
+        signature.push(b')');
+
+        let hash = sp_io::hashing::keccak_256(&signature);
+        Self {
+            selector: [hash[0], hash[1], hash[2], hash[3]],
+            name: name.to_vec(),
+            params,
+        }
+    }
+}
+
+/// A decoded EVM custom error.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct EvmCustomError {
+    /// The error name.
+    pub name: Vec<u8>,
+    /// The decoded error parameters.
+    pub params: Vec<EvmAbiValue>,
+}
+
+/// Decode EVM revert data as a custom error, if its selector matches one of `known_errors`.
+///
+/// Returns `None` if the selector is unknown, or the parameters don't decode as the
+/// parameter types of the matched error.
+pub fn decode_evm_custom_error(
+    data: &[u8],
+    known_errors: &[EvmCustomErrorSignature],
+) -> Option<EvmCustomError> {
+    let selector = data.get(..4)?;
+    let signature = known_errors
+        .iter()
+        .find(|signature| signature.selector[..] == *selector)?;
+
+    let payload = &data[4..];
+    if Some(payload.len()) != signature.params.len().checked_mul(32) {
+        return None;
+    }
+    let params = signature
+        .params
+        .iter()
+        .zip(payload.chunks_exact(32))
+        .map(|(param, word)| param.decode(word))
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(EvmCustomError {
+        name: signature.name.clone(),
+        params,
+    })
+}
+
+/// Decode WASM revert data as the error type `E` of the called message.
+///
+/// By convention, an ink! message returning `Result<T, E>` reverts with `Ok(Err(error))`
+/// SCALE encoded, the outer `Result` being for ink! language errors. Returns `None` if the
+/// revert data doesn't follow the convention, or `error` doesn't decode as `E`.
+pub fn decode_wasm_revert_error<E: Decode>(data: &[u8]) -> Option<E> {
+    match data {
+        [0x00, 0x01, error @ ..] => E::decode_all(&mut &error[..]).ok(),
+        _ => None,
+    }
+}
+
+/// The address of a contract deployed by `deployer` with `CREATE2`, as specified in EIP-1014:
+/// `keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`.
+///
+/// For callers to pre-compute deterministic deployment addresses.
+pub fn evm_create2_address(deployer: H160, salt: H256, init_code: &[u8]) -> H160 {
+    let mut preimage = Vec::with_capacity(1 + 20 + 32 + 32);
+    preimage.push(0xff);
+    preimage.extend_from_slice(deployer.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(&sp_io::hashing::keccak_256(init_code));
+    H160::from_slice(&sp_io::hashing::keccak_256(&preimage)[12..])
+}
+
+/// Convert a `Balance` to an EVM `uint256` value, never overflows.
+pub fn balance_to_u256(value: Balance) -> U256 {
+    U256::from(value)
+}
+
+/// Convert an EVM `uint256` value to `Balance`.
+///
+/// Fails with `BalanceConversionOverflow` if the value is above `Balance::MAX`, instead
+/// of truncating it.
+pub fn balance_from_u256(value: U256, used_weight: Weight) -> Result<Balance, CallFailure> {
+    Balance::try_from(value)
+        .map_err(|_| CallFailure::error(FailureError::BalanceConversionOverflow, used_weight))
+}
+
+/// Split `total` proportionally to `weights`, one value per weight.
+///
+/// Each value is rounded down, and the rounding remainder goes to the last non-zero weight,
+/// so the values exactly add up to `total` unless all weights are zero, in which case they
+/// are all zero.
+pub fn split_value(total: Balance, weights: &[u32]) -> Vec<Balance> {
+    let weight_sum: Balance = weights.iter().map(|weight| Balance::from(*weight)).sum();
+    if weight_sum == 0 {
+        return weights.iter().map(|_| 0).collect();
+    }
+
+    // `total * weight / weight_sum`, without overflowing `total * weight`.
+    let (quotient, remainder) = (total / weight_sum, total % weight_sum);
+    let mut values: Vec<Balance> = weights
+        .iter()
+        .map(|weight| {
+            let weight = Balance::from(*weight);
+            quotient
+                .saturating_mul(weight)
+                .saturating_add(remainder.saturating_mul(weight) / weight_sum)
+        })
+        .collect();
+
+    let split: Balance = values.iter().sum();
+    if let Some(last) = weights.iter().rposition(|weight| *weight > 0) {
+        values[last] = values[last].saturating_add(total.saturating_sub(split));
+    }
+    values
+}
+
+/// XVM call simulation result.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct XvmSimulationResult {
+    /// Output on success, or revert data on VM revert.
+    pub output: Vec<u8>,
+    /// Actual used weight.
+    pub used_weight: Weight,
+    /// Failure reason, `None` if the call succeeded.
+    pub failure: Option<FailureReason>,
+    /// Revert reason decoded from EVM `Error(string)` revert data.
+    pub revert_reason: Option<Vec<u8>>,
+    /// Custom error decoded from EVM revert data, if its selector is a known one. Only set
+    /// by the XVM pallet, which has the known custom errors.
+    pub custom_error: Option<EvmCustomError>,
+    /// Gas used by the top-level EVM frame, excluding its internal calls. Only set for
+    /// EVM calls if EVM tracing is enabled.
+    pub top_level_gas_used: Option<U256>,
+    /// Whether any precompile was called during the EVM execution. Only set for EVM
+    /// calls if EVM tracing is enabled.
+    pub touched_precompile: Option<bool>,
+    /// VMs in the order they were called, starting from the source VM, including nested
+    /// calls. A nested call denied by the reentrance guard is still included, as the last
+    /// transition.
+    pub vm_transition_path: Vec<VmId>,
+    /// Human readable used vs. limit weight, and gas for EVM calls. Only set by the XVM
+    /// pallet for `OutOfGas` failures.
+    pub weight_diagnostic: Option<Vec<u8>>,
+    /// Every call of the simulation, nested ones included, in the order they were made.
+    /// Only recorded if `Context::record_call_graph` is set.
+    pub call_graph: Vec<CallFrame>,
+}
+
+/// A call recorded in a XVM call graph, see `Context::record_call_graph`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct CallFrame {
+    /// Nesting depth of the call, `0` for the top-level call.
+    pub depth: u32,
+    /// The VM Id of the target contract.
+    pub vm_id: VmId,
+    /// SCALE encoded caller Id.
+    pub source: Vec<u8>,
+    /// Target contract address.
+    pub target: Vec<u8>,
+    /// Value to transfer.
+    pub value: Balance,
+    /// Actual used weight.
+    pub used_weight: Weight,
+    /// Failure reason, `None` if the call succeeded.
+    pub failure: Option<FailureReason>,
+}
+
+impl From<CallResult> for XvmSimulationResult {
+    fn from(result: CallResult) -> Self {
+        match result {
+            Ok(output) => Self {
+                output: output.output,
+                used_weight: output.used_weight,
+                failure: None,
+                revert_reason: None,
+                custom_error: None,
+                top_level_gas_used: None,
+                touched_precompile: None,
+                vm_transition_path: Vec::new(),
+                weight_diagnostic: None,
+                call_graph: Vec::new(),
+            },
+            Err(failure) => {
+                let output = match failure.reason {
+                    FailureReason::Revert(FailureRevert::VmRevert(ref data)) => data.clone(),
+                    _ => Vec::new(),
+                };
+                Self {
+                    revert_reason: decode_evm_revert_reason(&output),
+                    custom_error: None,
+                    output,
+                    used_weight: failure.used_weight,
+                    failure: Some(failure.reason),
+                    top_level_gas_used: None,
+                    touched_precompile: None,
+                    vm_transition_path: Vec::new(),
+                    weight_diagnostic: None,
+                    call_graph: Vec::new(),
+                }
+            }
+        }
+    }
+}
+
+/// XVM context.
+///
+/// Note this should be set by runtime, instead of passed by callers.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct Context {
+    /// The source VM of the call.
+    pub source_vm_id: VmId,
+    /// Max weight limit.
+    pub weight_limit: Weight,
+    /// Charge the WASM storage deposit against the call value. The storage deposit limit
+    /// is kept out of `value` for the deposit, and only the rest is transferred. Ignored
+    /// for EVM calls.
+    pub storage_deposit_from_value: bool,
+    /// Correlation id to trace the call across VMs in logs and events, zeroed if not
+    /// supplied. Nested calls inherit the trace id of the outermost call.
+    pub trace_id: [u8; 16],
+    /// Discard the output on success, for calls which ignore it. The revert data is kept.
+    pub discard_output: bool,
+    /// Record every call, nested ones included, as a `CallFrame`, to debug cross-VM call
+    /// flows. Only honoured on the top-level call of call simulations, returned as
+    /// `call_graph`. Ignored by on-chain calls.
+    pub record_call_graph: bool,
+}
+
+/// Invalid `Context` built by `ContextBuilder`.
+#[derive(PartialEq, Eq, Clone, Copy, RuntimeDebug)]
+pub enum ContextError {
+    /// Both components of the weight limit are zero, no call could be executed.
+    ZeroWeightLimit,
+}
+
+/// Builder of a `Context`, with defaults for the fields not set.
+///
+/// By default the weight limit is zero, which must be set, and the flags are unset with
+/// a zeroed trace id.
+#[derive(PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct ContextBuilder {
+    context: Context,
+}
+
+impl ContextBuilder {
+    /// Create a new builder of the context of calls from `source_vm_id`.
+    pub fn new(source_vm_id: VmId) -> Self {
+        Self {
+            context: Context {
+                source_vm_id,
+                weight_limit: Weight::zero(),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+        }
+    }
+
+    /// Set the max weight limit.
+    pub fn weight_limit(mut self, weight_limit: Weight) -> Self {
+        self.context.weight_limit = weight_limit;
+        self
+    }
+
+    /// Set whether to charge the WASM storage deposit against the call value.
+    pub fn storage_deposit_from_value(mut self, storage_deposit_from_value: bool) -> Self {
+        self.context.storage_deposit_from_value = storage_deposit_from_value;
+        self
+    }
+
+    /// Set the trace id.
+    pub fn trace_id(mut self, trace_id: [u8; 16]) -> Self {
+        self.context.trace_id = trace_id;
+        self
+    }
+
+    /// Set whether to discard the output on success.
+    pub fn discard_output(mut self, discard_output: bool) -> Self {
+        self.context.discard_output = discard_output;
+        self
+    }
+
+    /// Set whether to record the call graph.
+    pub fn record_call_graph(mut self, record_call_graph: bool) -> Self {
+        self.context.record_call_graph = record_call_graph;
+        self
+    }
+
+    /// Build the context, fails with `ZeroWeightLimit` if the weight limit is zero.
+    pub fn build(self) -> Result<Context, ContextError> {
+        if self.context.weight_limit == Weight::zero() {
+            return Err(ContextError::ZeroWeightLimit);
+        }
+        Ok(self.context)
+    }
+}
+
+/// A single call in a XVM call batch.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct BatchCall {
+    /// The VM Id of the target contract.
+    pub vm_id: VmId,
+    /// Target contract address.
+    pub target: Vec<u8>,
+    /// Call input data.
+    pub input: Vec<u8>,
+    /// Value to transfer.
+    pub value: Balance,
+    /// Storage deposit limit for wasm calls.
+    pub storage_deposit_limit: Option<Balance>,
+}
+
+/// Output of a XVM call batch.
+#[derive(PartialEq, Eq, Clone, RuntimeDebug)]
+pub struct BatchOutput {
+    /// Index in the batch and result of each call, in order.
+    pub results: Vec<(usize, CallResult)>,
+    /// Total weight of the batch, the batch overheads included.
+    pub total_weight: Weight,
+}
+
+/// A single independent call of `call_many`, with its own context and limits.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug, TypeInfo)]
+pub struct XvmCallSpec {
+    /// XVM context of the call, its weight limit included.
+    pub context: Context,
+    /// The VM Id of the target contract.
+    pub vm_id: VmId,
+    /// Target contract address.
+    pub target: Vec<u8>,
+    /// Call input data.
+    pub input: Vec<u8>,
+    /// Value to transfer.
+    pub value: Balance,
+    /// Storage deposit limit for wasm calls.
+    pub storage_deposit_limit: Option<Balance>,
+}
+
+pub trait XvmCall<AccountId> {
+    /// Call a contract in XVM.
+    ///
+    /// Parameters:
+    /// - `context`: XVM context.
+    /// - `vm_id`: the VM Id of the target contract.
+    /// - `source`: Caller Id.
+    /// - `target`: Target contract address.
+    /// - `input`: call input data.
+    /// - `value`: value to transfer.
+    /// - `storage_deposit_limit`: storage deposit limit for wasm calls. The storage
+    ///   deposit is charged to `source`, fails with `OutOfStorageDeposit` if exceeded.
+    fn call(
+        context: Context,
+        vm_id: VmId,
+        source: AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        storage_deposit_limit: Option<Balance>,
+    ) -> CallResult;
+
+    /// Call a contract in XVM, or only estimate the call if `dry_run` is `true`.
+    ///
+    /// Same parameters as `call`. In dry-run mode the call is decoded and validated,
+    /// so failures like `InvalidTarget` or `InputTooLarge` are still returned, but
+    /// returns before entering the VM with the XVM overheads as used weight.
+    fn call_with_dry_run(
+        context: Context,
+        vm_id: VmId,
+        source: AccountId,
+        target: Vec<u8>,
+        input: Vec<u8>,
+        value: Balance,
+        storage_deposit_limit: Option<Balance>,
+        dry_run: bool,
+    ) -> CallResult;
+
+    /// The XVM overheads of calling into `vm_id`, included in the used weight of calls.
+    fn call_overheads(_vm_id: VmId) -> Weight {
+        Weight::zero()
+    }
+}
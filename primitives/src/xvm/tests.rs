@@ -0,0 +1,660 @@
+// This file is part of Astar.
+
+// Copyright (C) 2019-2023 Stake Technologies Pte.Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// Astar is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Astar is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Astar. If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+#[test]
+fn xvm_outcome_from_evm_success() {
+    let result: CallResult = Ok(CallOutput::new(vec![1, 2, 3], Weight::from_parts(1, 1)));
+    assert_eq!(XvmOutcome::from(result), XvmOutcome::Success(vec![1, 2, 3]));
+}
+
+#[test]
+fn xvm_outcome_from_wasm_revert() {
+    let result: CallResult = Err(CallFailure::revert(
+        FailureRevert::VmRevert(vec![0, 1, 7]),
+        Weight::from_parts(1, 1),
+    ));
+    assert_eq!(
+        XvmOutcome::from(result),
+        XvmOutcome::Revert(NormalizedRevert::Vm(vec![0, 1, 7]))
+    );
+
+    let result: CallResult = Err(CallFailure::revert(
+        FailureRevert::InvalidTarget,
+        Weight::from_parts(1, 1),
+    ));
+    assert_eq!(
+        XvmOutcome::from(result),
+        XvmOutcome::Revert(NormalizedRevert::Rejected(FailureRevert::InvalidTarget))
+    );
+}
+
+#[test]
+fn xvm_outcome_from_evm_error() {
+    let result: CallResult = Err(CallFailure::error(
+        FailureError::VmError(b"EVM call error: OutOfFund".to_vec()),
+        Weight::from_parts(1, 1),
+    ));
+    assert_eq!(
+        XvmOutcome::from(result),
+        XvmOutcome::Error(NormalizedError::Vm(b"EVM call error: OutOfFund".to_vec()))
+    );
+
+    let result: CallResult = Err(CallFailure::error(
+        FailureError::ReentranceDenied,
+        Weight::from_parts(1, 1),
+    ));
+    assert_eq!(
+        XvmOutcome::from(result),
+        XvmOutcome::Error(NormalizedError::Rejected(FailureError::ReentranceDenied))
+    );
+}
+
+fn evm_error_msg(reason: &[u8], len: usize) -> Vec<u8> {
+    let mut data = EVM_ERROR_MSG_SELECTOR.to_vec();
+    let mut word = [0u8; 32];
+    U256::from(32).to_big_endian(&mut word);
+    data.extend_from_slice(&word);
+    U256::from(len).to_big_endian(&mut word);
+    data.extend_from_slice(&word);
+    data.extend_from_slice(reason);
+    data.resize(data.len() + (32 - reason.len() % 32) % 32, 0);
+    data
+}
+
+#[test]
+fn decode_evm_revert_reason_works() {
+    let reason = b"Not enough Ether provided.";
+    let data = evm_error_msg(reason, reason.len());
+    assert_eq!(decode_evm_revert_reason(&data), Some(reason.to_vec()));
+}
+
+#[test]
+fn decode_evm_revert_reason_rejects_invalid_data() {
+    // Empty or custom error data.
+    assert_eq!(decode_evm_revert_reason(&[]), None);
+    assert_eq!(decode_evm_revert_reason(&[1, 2, 3, 4]), None);
+    // Length out of bounds.
+    let data = evm_error_msg(b"Not enough Ether provided.", 0xFF);
+    assert_eq!(decode_evm_revert_reason(&data), None);
+}
+
+fn erc20_insufficient_balance() -> EvmCustomErrorSignature {
+    EvmCustomErrorSignature::new(
+        b"ERC20InsufficientBalance",
+        vec![
+            EvmAbiType::Address,
+            EvmAbiType::Uint256,
+            EvmAbiType::Uint256,
+        ],
+    )
+}
+
+/// `ERC20InsufficientBalance(0x0101..01, 100, 200)` revert data.
+fn erc20_insufficient_balance_data() -> Vec<u8> {
+    let mut data = vec![0xe4, 0x50, 0xd3, 0x8c];
+    data.extend_from_slice(&[0; 12]);
+    data.extend_from_slice(&[1; 20]);
+    let mut word = [0u8; 32];
+    U256::from(100).to_big_endian(&mut word);
+    data.extend_from_slice(&word);
+    U256::from(200).to_big_endian(&mut word);
+    data.extend_from_slice(&word);
+    data
+}
+
+#[test]
+fn evm_custom_error_signature_calculates_selector() {
+    assert_eq!(
+        erc20_insufficient_balance().selector,
+        [0xe4, 0x50, 0xd3, 0x8c]
+    );
+    assert_eq!(
+        EvmCustomErrorSignature::new(b"Unauthorized", vec![]).selector,
+        [0x82, 0xb4, 0x29, 0x00]
+    );
+}
+
+#[test]
+fn decode_evm_custom_error_works() {
+    let known_errors = vec![
+        EvmCustomErrorSignature::new(b"Unauthorized", vec![]),
+        erc20_insufficient_balance(),
+    ];
+    assert_eq!(
+        decode_evm_custom_error(&erc20_insufficient_balance_data(), &known_errors),
+        Some(EvmCustomError {
+            name: b"ERC20InsufficientBalance".to_vec(),
+            params: vec![
+                EvmAbiValue::Address(H160::repeat_byte(1)),
+                EvmAbiValue::Uint256(U256::from(100)),
+                EvmAbiValue::Uint256(U256::from(200)),
+            ],
+        })
+    );
+
+    let unauthorized = EvmCustomErrorSignature::new(b"Unauthorized", vec![]);
+    assert_eq!(
+        decode_evm_custom_error(&unauthorized.selector, &known_errors),
+        Some(EvmCustomError {
+            name: b"Unauthorized".to_vec(),
+            params: vec![],
+        })
+    );
+}
+
+#[test]
+fn decode_evm_custom_error_rejects_invalid_data() {
+    let known_errors = vec![erc20_insufficient_balance()];
+    let data = erc20_insufficient_balance_data();
+
+    // Unknown selector, including `Error(string)`.
+    assert_eq!(decode_evm_custom_error(&data, &[]), None);
+    let data_msg = evm_error_msg(b"oops", 4);
+    assert_eq!(decode_evm_custom_error(&data_msg, &known_errors), None);
+    assert_eq!(decode_evm_custom_error(&[0xe4, 0x50], &known_errors), None);
+    // Truncated or extra parameters.
+    assert_eq!(
+        decode_evm_custom_error(&data[..data.len() - 1], &known_errors),
+        None
+    );
+    let mut extra = data.clone();
+    extra.extend_from_slice(&[0; 32]);
+    assert_eq!(decode_evm_custom_error(&extra, &known_errors), None);
+    // Dirty address padding.
+    let mut dirty = data;
+    dirty[4] = 1;
+    assert_eq!(decode_evm_custom_error(&dirty, &known_errors), None);
+}
+
+#[test]
+fn simulation_result_decodes_evm_revert_reason() {
+    let data = evm_error_msg(b"oops", 4);
+    let result: CallResult = Err(CallFailure::revert(
+        FailureRevert::VmRevert(data.clone()),
+        Weight::from_parts(1, 1),
+    ));
+    assert_eq!(
+        XvmSimulationResult::from(result),
+        XvmSimulationResult {
+            output: data.clone(),
+            used_weight: Weight::from_parts(1, 1),
+            failure: Some(FailureReason::Revert(FailureRevert::VmRevert(data))),
+            revert_reason: Some(b"oops".to_vec()),
+            custom_error: None,
+            top_level_gas_used: None,
+            touched_precompile: None,
+            vm_transition_path: Vec::new(),
+            weight_diagnostic: None,
+            call_graph: Vec::new(),
+        }
+    );
+}
+
+#[test]
+fn validated_output_works() {
+    let output = CallOutput::new(42u32.encode(), Weight::from_parts(1, 1));
+    assert_eq!(output.clone().validated(scale_decodable::<u32>), Ok(output));
+
+    let output = CallOutput::new(vec![0u8; 64], Weight::from_parts(1, 1));
+    assert_eq!(output.clone().validated(abi_decodable(2)), Ok(output));
+}
+
+#[test]
+fn validated_output_fails_if_malformed() {
+    let output = CallOutput::new(vec![1, 2, 3], Weight::from_parts(1, 1));
+    assert_eq!(
+        output.validated(scale_decodable::<u32>),
+        Err(CallFailure::revert(
+            FailureRevert::OutputDecodeFailed,
+            Weight::from_parts(1, 1)
+        ))
+    );
+    // Trailing bytes.
+    let output = CallOutput::new(vec![1, 2, 3, 4, 5], Weight::from_parts(1, 1));
+    assert!(output.validated(scale_decodable::<u32>).is_err());
+    let output = CallOutput::new(vec![0u8; 63], Weight::from_parts(1, 1));
+    assert!(output.validated(abi_decodable(2)).is_err());
+}
+
+#[test]
+fn compact_call_result_round_trip() {
+    let success: CallResult = Ok(CallOutput::new(vec![1, 2, 3], Weight::from_parts(1, 2)));
+    let encoded = success.encode_compact();
+    assert!(encoded.len() < success.encode().len());
+    assert_eq!(CallResult::decode_compact(&encoded).ok(), Some(success));
+
+    let failure: CallResult = Err(CallFailure::revert(
+        FailureRevert::VmRevert(vec![0, 1, 7]),
+        Weight::from_parts(100, 200),
+    ));
+    assert_eq!(
+        CallResult::decode_compact(&failure.encode_compact()).ok(),
+        Some(failure)
+    );
+
+    let failure: CallResult = Err(CallFailure::error(
+        FailureError::VmError(b"oops".to_vec()),
+        Weight::from_parts(100, 200),
+    ));
+    assert_eq!(
+        CallResult::decode_compact(&failure.encode_compact()).ok(),
+        Some(failure)
+    );
+}
+
+#[test]
+fn compact_call_result_fails_if_malformed() {
+    let mut encoded =
+        CallResult::Ok(CallOutput::new(vec![1, 2, 3], Weight::zero())).encode_compact();
+    assert!(CallResult::decode_compact(&encoded[1..]).is_err());
+    encoded.push(0);
+    assert!(CallResult::decode_compact(&encoded).is_err());
+}
+
+#[test]
+fn xvm_target_decoding_works() {
+    let evm_target = H160::repeat_byte(1);
+    let wasm_target = [2u8; 32];
+
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Evm, evm_target.encode())),
+        Ok(XvmTarget::Evm(evm_target))
+    );
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Wasm, wasm_target.encode())),
+        Ok(XvmTarget::Wasm(wasm_target))
+    );
+}
+
+#[test]
+fn xvm_target_decoding_fails_if_misencoded() {
+    let evm_target = H160::repeat_byte(1);
+    let wasm_target = [2u8; 32];
+
+    // Wrong VM.
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Evm, wasm_target.encode())),
+        Err(FailureRevert::TargetVmMismatch)
+    );
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Wasm, evm_target.encode())),
+        Err(FailureRevert::TargetVmMismatch)
+    );
+    // Wrong length.
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Evm, vec![1; 19])),
+        Err(FailureRevert::InvalidTarget)
+    );
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Evm, vec![1; 21])),
+        Err(FailureRevert::InvalidTarget)
+    );
+    // Empty EVM target, as for contract creation.
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Evm, vec![])),
+        Err(FailureRevert::EmptyTarget)
+    );
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Wasm, vec![])),
+        Err(FailureRevert::InvalidTarget)
+    );
+    assert_eq!(
+        XvmTarget::<[u8; 32]>::try_from((VmId::Wasm, vec![2; 31])),
+        Err(FailureRevert::InvalidTarget)
+    );
+    // Same target length for both VMs.
+    assert_eq!(
+        XvmTarget::<[u8; 20]>::try_from((VmId::Wasm, vec![2; 20])),
+        Ok(XvmTarget::Wasm([2; 20]))
+    );
+}
+
+#[test]
+fn into_dispatch_result_reports_used_weight_on_success() {
+    let used_weight = Weight::from_parts(10, 1);
+    let result: CallResult = Ok(CallOutput::new(vec![1, 2, 3], used_weight));
+    assert_eq!(
+        into_dispatch_result(
+            result,
+            DispatchError::Other("error"),
+            DispatchError::Other("revert")
+        ),
+        Ok(PostDispatchInfo {
+            actual_weight: Some(used_weight),
+            pays_fee: Default::default(),
+        })
+    );
+}
+
+#[test]
+fn into_dispatch_result_maps_failures() {
+    let used_weight = Weight::from_parts(10, 1);
+    let post_info = PostDispatchInfo {
+        actual_weight: Some(used_weight),
+        pays_fee: Default::default(),
+    };
+
+    // error
+    let result: CallResult = Err(CallFailure::error(
+        FailureError::VmError(b"error".to_vec()),
+        used_weight,
+    ));
+    assert_eq!(
+        into_dispatch_result(
+            result,
+            DispatchError::Other("error"),
+            DispatchError::Other("revert")
+        ),
+        Err(DispatchErrorWithPostInfo {
+            post_info,
+            error: DispatchError::Other("error"),
+        })
+    );
+
+    // revert
+    let result: CallResult = Err(CallFailure::revert(
+        FailureRevert::VmRevert(vec![0, 1, 7]),
+        used_weight,
+    ));
+    assert_eq!(
+        into_dispatch_result(
+            result,
+            DispatchError::Other("error"),
+            DispatchError::Other("revert")
+        ),
+        Err(DispatchErrorWithPostInfo {
+            post_info,
+            error: DispatchError::Other("revert"),
+        })
+    );
+}
+
+#[test]
+fn weight_breakdown_adds_up() {
+    let requested = Weight::from_parts(100, 100);
+    let overheads = Weight::from_parts(10, 0);
+
+    let result: CallResult = Ok(CallOutput::new(vec![], Weight::from_parts(30, 20)));
+    assert_eq!(
+        WeightBreakdown::new(requested, overheads, &result),
+        WeightBreakdown {
+            requested,
+            overheads,
+            execution: Weight::from_parts(20, 20),
+            total_used: Weight::from_parts(30, 20),
+        }
+    );
+    assert_eq!(
+        WeightBreakdown::new(requested, overheads, &result).unused(),
+        Weight::from_parts(70, 80)
+    );
+
+    // Denied before entering the VM.
+    let result: CallResult = Err(CallFailure::denied(
+        FailureError::ReentranceDenied,
+        overheads,
+        Weight::from_parts(4, 0),
+    ));
+    let breakdown = WeightBreakdown::new(requested, overheads, &result);
+    assert_eq!(breakdown.overheads, Weight::from_parts(4, 0));
+    assert_eq!(breakdown.execution, Weight::zero());
+    assert_eq!(breakdown.unused(), Weight::from_parts(96, 100));
+}
+
+#[test]
+fn evm_call_status_maps_success_reasons() {
+    assert_eq!(
+        evm_call_status(ExitSucceed::Returned),
+        call_status::RETURNED
+    );
+    assert_eq!(evm_call_status(ExitSucceed::Stopped), call_status::STOPPED);
+    assert_eq!(
+        evm_call_status(ExitSucceed::Suicided),
+        call_status::SUICIDED
+    );
+
+    // Zero by default.
+    let output = CallOutput::new(vec![1, 2, 3], Weight::from_parts(1, 1));
+    assert_eq!(output.status, call_status::RETURNED);
+    assert_eq!(output.used_gas, None);
+    assert_eq!(output.storage_deposit, None);
+    assert_eq!(output.with_status(7).status, 7);
+}
+
+#[test]
+fn vm_id_u8_discriminant_round_trips() {
+    assert_eq!(VmId::Evm.as_u8(), 0);
+    assert_eq!(VmId::Wasm.as_u8(), 1);
+    for vm_id in [VmId::Evm, VmId::Wasm] {
+        assert_eq!(VmId::from_u8(vm_id.as_u8()), Some(vm_id));
+    }
+
+    // Unknown discriminants, including the `repr(u8)` values.
+    for n in [2, VmId::Evm as u8, VmId::Wasm as u8] {
+        assert_eq!(VmId::from_u8(n), None);
+    }
+}
+
+#[test]
+fn vm_id_from_str_round_trips() {
+    for vm_id in [VmId::Evm, VmId::Wasm] {
+        assert_eq!(VmId::from_str(&vm_id.to_string()), Ok(vm_id));
+    }
+    assert_eq!("EVM".parse::<VmId>(), Ok(VmId::Evm));
+    assert_eq!("Wasm".parse::<VmId>(), Ok(VmId::Wasm));
+
+    assert_eq!(
+        "ink".parse::<VmId>(),
+        Err(FailureReason::Error(FailureError::InvalidVmId))
+    );
+    assert_eq!(
+        "".parse::<VmId>(),
+        Err(FailureReason::Error(FailureError::InvalidVmId))
+    );
+}
+
+#[test]
+fn evm_create2_address_works() {
+    // EIP-1014 examples 0 and 1.
+    assert_eq!(
+        evm_create2_address(H160::zero(), H256::zero(), &[0x00]),
+        H160::from_str("4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38").unwrap(),
+    );
+    let deployer = H160::from_str("deadbeef00000000000000000000000000000000").unwrap();
+    assert_eq!(
+        evm_create2_address(deployer, H256::zero(), &[0x00]),
+        H160::from_str("b928f69bb1d91cd65274e3c79d8986362984fda3").unwrap(),
+    );
+
+    // Same init code with different salts.
+    assert_ne!(
+        evm_create2_address(deployer, H256::zero(), &[0x00]),
+        evm_create2_address(deployer, H256::from_low_u64_be(1), &[0x00]),
+    );
+}
+
+#[derive(PartialEq, Eq, Debug, Encode, Decode)]
+enum WasmContractError {
+    InsufficientBalance { required: u128 },
+    Unauthorized,
+}
+
+#[test]
+fn decode_wasm_revert_error_works() {
+    let error = WasmContractError::InsufficientBalance { required: 42 };
+    let data = Ok::<Result<(), _>, u8>(Err(error)).encode();
+    assert_eq!(
+        decode_wasm_revert_error::<WasmContractError>(&data),
+        Some(WasmContractError::InsufficientBalance { required: 42 })
+    );
+    assert_eq!(
+        decode_wasm_revert_error::<WasmContractError>(&[0x00, 0x01, 0x01]),
+        Some(WasmContractError::Unauthorized)
+    );
+
+    // Not `Ok(Err(_))`.
+    assert_eq!(
+        decode_wasm_revert_error::<WasmContractError>(&[0x01, 0x01, 0x01]),
+        None
+    );
+    assert_eq!(decode_wasm_revert_error::<WasmContractError>(&[]), None);
+    // Unknown variant and trailing bytes.
+    assert_eq!(
+        decode_wasm_revert_error::<WasmContractError>(&[0x00, 0x01, 0x02]),
+        None
+    );
+    assert_eq!(
+        decode_wasm_revert_error::<WasmContractError>(&[0x00, 0x01, 0x01, 0x00]),
+        None
+    );
+}
+
+#[test]
+fn balance_from_u256_is_checked() {
+    let used_weight = Weight::from_parts(1, 1);
+    assert_eq!(
+        balance_from_u256(balance_to_u256(Balance::MAX), used_weight),
+        Ok(Balance::MAX)
+    );
+    assert_eq!(balance_from_u256(U256::from(42), used_weight), Ok(42));
+
+    let overflow = Err(CallFailure::error(
+        FailureError::BalanceConversionOverflow,
+        used_weight,
+    ));
+    assert_eq!(
+        balance_from_u256(U256::from(Balance::MAX) + 1, used_weight),
+        overflow
+    );
+    assert_eq!(balance_from_u256(U256::MAX, used_weight), overflow);
+}
+
+#[test]
+fn split_value_works() {
+    // Even split.
+    assert_eq!(split_value(300, &[1, 1, 1]), vec![100, 100, 100]);
+    // Uneven split, the remainder goes to the last call.
+    assert_eq!(split_value(100, &[1, 1, 1]), vec![33, 33, 34]);
+    assert_eq!(split_value(1_000, &[1, 2, 4]), vec![142, 285, 573]);
+    // Zero weight targets get nothing, the remainder goes to the last non-zero weight.
+    assert_eq!(split_value(100, &[1, 0, 2]), vec![33, 0, 67]);
+    assert_eq!(split_value(100, &[1, 2, 0]), vec![33, 67, 0]);
+    assert_eq!(split_value(100, &[0, 0]), vec![0, 0]);
+    assert_eq!(split_value(100, &[]), Vec::<Balance>::new());
+
+    // Never exceeds the total, nor overflows.
+    for total in [0, 1, 7, Balance::MAX - 1, Balance::MAX] {
+        let weights = [u32::MAX, 3, u32::MAX - 1, 1];
+        let values = split_value(total, &weights);
+        assert_eq!(values.iter().sum::<Balance>(), total);
+    }
+}
+
+#[test]
+fn context_builder_works() {
+    let weight_limit = Weight::from_parts(1_000_000, 1_000_000);
+    assert_eq!(
+        ContextBuilder::new(VmId::Evm)
+            .weight_limit(weight_limit)
+            .build(),
+        Ok(Context {
+            source_vm_id: VmId::Evm,
+            weight_limit,
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output: false,
+            record_call_graph: false,
+        })
+    );
+    assert_eq!(
+        ContextBuilder::new(VmId::Wasm)
+            .weight_limit(weight_limit)
+            .storage_deposit_from_value(true)
+            .trace_id([7; 16])
+            .discard_output(true)
+            .record_call_graph(true)
+            .build(),
+        Ok(Context {
+            source_vm_id: VmId::Wasm,
+            weight_limit,
+            storage_deposit_from_value: true,
+            trace_id: [7; 16],
+            discard_output: true,
+            record_call_graph: true,
+        })
+    );
+
+    // Only a partially zero weight limit is accepted.
+    assert!(ContextBuilder::new(VmId::Evm)
+        .weight_limit(Weight::from_parts(1_000_000, 0))
+        .build()
+        .is_ok());
+}
+
+#[test]
+fn context_builder_rejects_zero_weight_limit() {
+    assert_eq!(
+        ContextBuilder::new(VmId::Evm).build(),
+        Err(ContextError::ZeroWeightLimit)
+    );
+    assert_eq!(
+        ContextBuilder::new(VmId::Wasm)
+            .weight_limit(Weight::zero())
+            .build(),
+        Err(ContextError::ZeroWeightLimit)
+    );
+}
+
+#[test]
+fn exit_category_from_exit_reason_works() {
+    use fp_evm::{ExitError, ExitFatal, ExitRevert};
+
+    assert_eq!(
+        ExitCategory::from(&ExitReason::Succeed(ExitSucceed::Returned)),
+        ExitCategory::Succeed
+    );
+    assert_eq!(
+        ExitCategory::from(&ExitReason::Revert(ExitRevert::Reverted)),
+        ExitCategory::Revert
+    );
+    assert_eq!(
+        ExitCategory::from(&ExitReason::Error(ExitError::OutOfGas)),
+        ExitCategory::Error
+    );
+    assert_eq!(
+        ExitCategory::from(&ExitReason::Fatal(ExitFatal::NotSupported)),
+        ExitCategory::Fatal
+    );
+
+    // Follows the failure reason by default.
+    let used_weight = Weight::from_parts(1, 1);
+    assert_eq!(
+        CallFailure::revert(FailureRevert::InvalidTarget, used_weight).category,
+        ExitCategory::Revert
+    );
+    assert_eq!(
+        CallFailure::error(FailureError::OutOfGas, used_weight).category,
+        ExitCategory::Error
+    );
+    assert_eq!(
+        CallFailure::denied(FailureError::ReentranceDenied, used_weight, used_weight).category,
+        ExitCategory::Error
+    );
+}
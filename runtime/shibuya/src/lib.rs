@@ -789,11 +789,73 @@ impl pallet_ethereum_checked::Config for Runtime {
     type WeightInfo = pallet_ethereum_checked::weights::SubstrateWeight<Runtime>;
 }
 
+parameter_types! {
+    pub const XvmMaxTransferValue: Option<Balance> = None;
+    // XVM calls below the existential deposit are handled by the VMs, as before the policy.
+    pub const XvmTargetExistentialDepositPolicy: pallet_xvm::ExistentialDepositPolicy =
+        pallet_xvm::ExistentialDepositPolicy::Allow;
+    pub const XvmWeightExhaustionThreshold: Option<Perbill> = Some(Perbill::from_percent(95));
+    pub const XvmPalletId: PalletId = PalletId(*b"py/xvmac");
+    pub const XvmStagedInputDepositPerByte: Balance = deposit(0, 1);
+}
+
+/// Matches the addresses of the runtime precompiles.
+pub struct IsPrecompile;
+impl Contains<H160> for IsPrecompile {
+    fn contains(address: &H160) -> bool {
+        Precompiles::used_addresses().any(|precompile| precompile == *address)
+    }
+}
+
 impl pallet_xvm::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
     type GasWeightMapping = pallet_evm::FixedGasWeightMapping<Self>;
+    type PalletId = XvmPalletId;
+    type PrimaryAccountMapping = ();
     type AccountMapping = UnifiedAccounts;
     type EthereumTransact = EthereumChecked;
+    type MaxAccessListEntries = ConstU32<1024>;
+    type MaxEvmOutputSize = ConstU32<{ 64 * 1024 }>;
+    type MaxWasmOutputSize = ConstU32<{ 64 * 1024 }>;
+    type TruncateOversizedOutput = ConstBool<false>;
+    type RequireExplicitGasLimit = ConstBool<false>;
+    type DenySelfCalls = ConstBool<false>;
+    type MaxTransferValue = XvmMaxTransferValue;
+    type TargetExistentialDepositPolicy = XvmTargetExistentialDepositPolicy;
+    type TrustedEvmSourceCallers = Nothing;
+    type TrustedRawEvmTxCallers = Nothing;
+    type IsPrecompile = IsPrecompile;
+    type AllowedEvmTargets = frame_support::traits::Everything;
+    type EvmCustomErrors = ();
+    type SelectorMap = ();
+    type BatchSummaryEvent = ConstBool<true>;
+    type WeightExhaustionThreshold = XvmWeightExhaustionThreshold;
+    type MaxDeferredCalls = ConstU32<16>;
+    type MaxDeferredDepth = ConstU32<4>;
+    type PauseOrigin = EnsureRoot<AccountId>;
+    type MaxInputSize = ConstU32<{ 256 * 1024 }>;
+    type StagedInputLifetime = ConstU32<{ 10 * MINUTES }>;
+    type StagedInputDepositPerByte = XvmStagedInputDepositPerByte;
+    type MaxCallsPerBlock = ConstU32<1_000>;
+    type MaxCallsPerTarget = ();
     type WeightInfo = pallet_xvm::weights::SubstrateWeight<Runtime>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = XvmBenchmarkHelper;
+}
+
+/// Funds the native account of EVM addresses, which holds their EVM balance.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct XvmBenchmarkHelper;
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_xvm::BenchmarkHelper for XvmBenchmarkHelper {
+    fn set_evm_balance(address: H160, balance: Balance) {
+        let account =
+            <UnifiedAccounts as pallet_evm::AddressMapping<AccountId>>::into_account_id(address);
+        let _ = <Balances as frame_support::traits::Currency<AccountId>>::make_free_balance_be(
+            &account, balance,
+        );
+    }
 }
 
 /// Current approximation of the gas/s consumption considering
@@ -1590,6 +1652,27 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_xvm::runtime_api::XvmRuntimeApi<Block, AccountId> for Runtime {
+        fn xvm_call(
+            context: astar_primitives::xvm::Context,
+            vm_id: astar_primitives::xvm::VmId,
+            source: AccountId,
+            target: Vec<u8>,
+            input: Vec<u8>,
+            value: Balance,
+        ) -> astar_primitives::xvm::XvmSimulationResult {
+            Xvm::simulate_call(context, vm_id, source, target, input, value)
+        }
+
+        fn xvm_account_mapping(account: AccountId) -> H160 {
+            Xvm::mapped_evm_address(account)
+        }
+
+        fn xvm_supported_vms() -> Vec<astar_primitives::xvm::VmId> {
+            Xvm::supported_vms()
+        }
+    }
+
     impl fp_rpc::EthereumRuntimeRPCApi<Block> for Runtime {
         fn chain_id() -> u64 {
             EVMChainId::get()
@@ -22,15 +22,22 @@ use sha3::{Digest, Keccak256};
 
 use astar_primitives::{
     ethereum_checked::{CheckedEthereumTransact, CheckedEthereumTx, EthereumTxInput},
-    xvm::{CallFailure, Context, FailureError, FailureReason, FailureRevert, VmId, XvmCall},
+    xvm::{
+        decode_wasm_revert_error, CallFailure, Context, FailureError, FailureReason, FailureRevert,
+        VmId, XvmCall,
+    },
 };
 use fp_evm::{ExecutionInfoV2, ExitReason, ExitRevert};
-use frame_support::{dispatch::PostDispatchInfo, traits::Currency, weights::Weight};
+use frame_support::{
+    dispatch::PostDispatchInfo, storage::with_transaction, traits::Currency, weights::Weight,
+};
 use pallet_contracts::{CollectEvents, DebugInfo, Determinism};
 use pallet_contracts_primitives::{ExecReturnValue, ReturnFlags};
-use parity_scale_codec::Encode;
+use pallet_xvm::test_utils::{self, XvmCallBuilder};
+use parity_scale_codec::{Decode, Encode};
 use precompile_utils::{Bytes, EvmDataWriter};
-use sp_runtime::MultiAddress;
+use sp_runtime::{DispatchError, MultiAddress, TransactionOutcome};
+use xvm_chain_extension_types::XvmFailure;
 
 // Build EVM revert message error data.
 fn evm_revert_message_error(msg: &str) -> Vec<u8> {
@@ -186,6 +193,10 @@ fn evm_payable_call_via_xvm_works() {
             Context {
                 source_vm_id: VmId::Wasm,
                 weight_limit: Weight::from_parts(1_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Evm,
             ALICE,
@@ -204,6 +215,10 @@ fn evm_payable_call_via_xvm_works() {
             Context {
                 source_vm_id: VmId::Wasm,
                 weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Evm,
             ALICE,
@@ -220,6 +235,254 @@ fn evm_payable_call_via_xvm_works() {
     });
 }
 
+#[cfg(feature = "evm-tracing")]
+#[test]
+fn simulate_evm_call_returns_top_level_gas_used() {
+    use pallet_evm::GasWeightMapping;
+
+    new_test_ext().execute_with(|| {
+        // create account mappings
+        connect_accounts(&ALICE, &alith_secret_key());
+
+        let evm_payable_addr = deploy_evm_contract(EVM_PAYABLE);
+
+        // `withdraw` makes an internal call to the contract owner.
+        let result = Xvm::simulate_call(
+            Context {
+                source_vm_id: VmId::Wasm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Evm,
+            ALICE,
+            evm_payable_addr.as_ref().to_vec(),
+            // `Calling withdraw`
+            hex::decode("3ccfd60b").expect("invalid selector hex"),
+            0,
+        );
+        assert_eq!(result.failure, None);
+
+        let total_gas_used = U256::from(
+            <Runtime as pallet_evm::Config>::GasWeightMapping::weight_to_gas(result.used_weight),
+        );
+        let top_level_gas_used = result.top_level_gas_used.expect("traced on EVM calls");
+        assert!(!top_level_gas_used.is_zero());
+        assert!(top_level_gas_used < total_gas_used);
+    });
+}
+
+#[test]
+#[cfg(feature = "evm-tracing")]
+fn simulate_evm_call_reports_touched_precompile() {
+    new_test_ext().execute_with(|| {
+        // create account mappings
+        connect_accounts(&ALICE, &alith_secret_key());
+
+        let evm_caller_addr = deploy_evm_contract(CALL_XVM_PAYABLE_WITH_SDL);
+        let context = Context {
+            source_vm_id: VmId::Wasm,
+            weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output: false,
+            record_call_graph: false,
+        };
+
+        // The call into the XVM precompile is denied due to reentrance, but it is still
+        // traced as touched.
+        let result = Xvm::simulate_call(
+            context.clone(),
+            VmId::Evm,
+            ALICE,
+            evm_caller_addr.as_ref().to_vec(),
+            // to: 0x0e0ddb5a5f0b99d7be468a3051a94073ec6b1900178316401a52b93415026999
+            // input: 0x0000002a (store)
+            // value: 0
+            // storage_deposit_limit: 1_000_000_000_000_000
+            hex::decode("2d9338da000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000c0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000038d7ea4c6800000000000000000000000000000000000000000000000000000000000000000200e0ddb5a5f0b99d7be468a3051a94073ec6b1900178316401a52b9341502699900000000000000000000000000000000000000000000000000000000000000040000002a00000000000000000000000000000000000000000000000000000000").expect("invalid call input hex"),
+            0,
+        );
+        assert_eq!(result.touched_precompile, Some(true));
+
+        // No precompile is called by `withdraw`.
+        let evm_payable_addr = deploy_evm_contract(EVM_PAYABLE);
+        let result = Xvm::simulate_call(
+            context,
+            VmId::Evm,
+            ALICE,
+            evm_payable_addr.as_ref().to_vec(),
+            // `Calling withdraw`
+            hex::decode("3ccfd60b").expect("invalid selector hex"),
+            0,
+        );
+        assert_eq!(result.touched_precompile, Some(false));
+    });
+}
+
+#[test]
+fn simulate_nested_call_returns_vm_transition_path() {
+    new_test_ext().execute_with(|| {
+        // create account mappings
+        connect_accounts(&ALICE, &alith_secret_key());
+
+        let evm_caller_addr = deploy_evm_contract(CALL_XVM_PAYABLE_WITH_SDL);
+
+        // WASM -> EVM -> WASM, the last hop is denied by the reentrance guard.
+        let result = Xvm::simulate_call(
+            Context {
+                source_vm_id: VmId::Wasm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Evm,
+            ALICE,
+            evm_caller_addr.as_ref().to_vec(),
+            // to: 0x0e0ddb5a5f0b99d7be468a3051a94073ec6b1900178316401a52b93415026999
+            // input: 0x0000002a (store)
+            // value: 0
+            // storage_deposit_limit: 1_000_000_000_000_000
+            hex::decode("2d9338da000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000c0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000038d7ea4c6800000000000000000000000000000000000000000000000000000000000000000200e0ddb5a5f0b99d7be468a3051a94073ec6b1900178316401a52b9341502699900000000000000000000000000000000000000000000000000000000000000040000002a00000000000000000000000000000000000000000000000000000000").expect("invalid call input hex"),
+            0,
+        );
+        assert_eq!(
+            result.vm_transition_path,
+            vec![VmId::Wasm, VmId::Evm, VmId::Wasm]
+        );
+    });
+}
+
+#[test]
+fn simulate_nested_call_records_call_graph() {
+    new_test_ext().execute_with(|| {
+        // create account mappings
+        connect_accounts(&ALICE, &alith_secret_key());
+
+        let evm_caller_addr = deploy_evm_contract(CALL_XVM_PAYABLE_WITH_SDL);
+
+        // WASM -> EVM -> WASM, the last hop is denied by the reentrance guard.
+        let result = Xvm::simulate_call(
+            Context {
+                source_vm_id: VmId::Wasm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: true,
+            },
+            VmId::Evm,
+            ALICE,
+            evm_caller_addr.as_ref().to_vec(),
+            // to: 0x0e0ddb5a5f0b99d7be468a3051a94073ec6b1900178316401a52b93415026999
+            // input: 0x0000002a (store)
+            // value: 0
+            // storage_deposit_limit: 1_000_000_000_000_000
+            hex::decode("2d9338da000000000000000000000000000000000000000000000000000000000000008000000000000000000000000000000000000000000000000000000000000000c0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000038d7ea4c6800000000000000000000000000000000000000000000000000000000000000000200e0ddb5a5f0b99d7be468a3051a94073ec6b1900178316401a52b9341502699900000000000000000000000000000000000000000000000000000000000000040000002a00000000000000000000000000000000000000000000000000000000").expect("invalid call input hex"),
+            0,
+        );
+        let frames = result.call_graph;
+        assert_eq!(frames.len(), 2);
+        assert_eq!((frames[0].depth, frames[0].vm_id), (0, VmId::Evm));
+        assert_eq!(frames[0].source, ALICE.encode());
+        assert_eq!(frames[0].target, evm_caller_addr.as_ref().to_vec());
+        assert_eq!((frames[1].depth, frames[1].vm_id), (1, VmId::Wasm));
+        assert_eq!(
+            frames[1].failure,
+            Some(FailureReason::Error(FailureError::ReentranceDenied))
+        );
+    });
+}
+
+#[test]
+fn evm_call_via_xvm_rolls_back_value_if_reverted() {
+    new_test_ext().execute_with(|| {
+        // create account mappings
+        connect_accounts(&ALICE, &alith_secret_key());
+
+        let evm_payable_addr = deploy_evm_contract(EVM_PAYABLE);
+        let prev_alice_balance = Balances::free_balance(&ALICE);
+
+        let result = Xvm::call(
+            Context {
+                source_vm_id: VmId::Wasm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Evm,
+            ALICE,
+            evm_payable_addr.as_ref().to_vec(),
+            // Calling `withdraw`, which isn't payable
+            hex::decode("3ccfd60b").expect("invalid selector hex"),
+            UNIT,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Revert(FailureRevert::VmRevert(_)),
+                ..
+            }) => {}
+            _ => panic!("unexpected evm call result: {:?}", result),
+        }
+        assert_eq!(Balances::free_balance(&ALICE), prev_alice_balance);
+        assert_eq!(Balances::free_balance(account_id_from(evm_payable_addr)), 0);
+    });
+}
+
+/* EVM storage clear
+
+Hand-assembled, the constructor sets slot 0 to 1 and the runtime code clears it:
+
+PUSH1 0x00 PUSH1 0x00 SSTORE STOP
+*/
+const EVM_STORAGE_CLEAR: &str = "600160005560066011600039600660006000f3600060005500";
+
+#[test]
+fn evm_call_via_xvm_charges_deterministic_weight_with_refund() {
+    new_test_ext().execute_with(|| {
+        // create account mappings
+        connect_accounts(&ALICE, &alith_secret_key());
+
+        let evm_storage_clear_addr = deploy_evm_contract(EVM_STORAGE_CLEAR);
+        let call = || {
+            Xvm::call(
+                Context {
+                    source_vm_id: VmId::Wasm,
+                    weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                    storage_deposit_from_value: false,
+                    trace_id: [0; 16],
+                    discard_output: false,
+                    record_call_graph: false,
+                },
+                VmId::Evm,
+                ALICE,
+                evm_storage_clear_addr.as_ref().to_vec(),
+                vec![],
+                0,
+                None,
+            )
+            .expect("storage clear never fails")
+            .used_weight
+        };
+
+        // Clearing the storage is refunded, the same weight is charged for the same state.
+        let refunded_weight =
+            with_transaction(|| TransactionOutcome::Rollback(Ok::<_, DispatchError>(call())))
+                .unwrap();
+        assert_eq!(call(), refunded_weight);
+
+        // The slot is already cleared, no refunds.
+        assert!(call().ref_time() > refunded_weight.ref_time());
+    });
+}
+
 #[test]
 fn wasm_payable_call_via_xvm_works() {
     new_test_ext().execute_with(|| {
@@ -231,6 +494,10 @@ fn wasm_payable_call_via_xvm_works() {
             Context {
                 source_vm_id: VmId::Evm,
                 weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Wasm,
             ALICE,
@@ -247,6 +514,46 @@ fn wasm_payable_call_via_xvm_works() {
     });
 }
 
+#[test]
+fn wasm_payable_call_with_staged_input_works() {
+    new_test_ext().execute_with(|| {
+        let wasm_payable_addr = deploy_wasm_contract(WASM_PAYABLE_NAME);
+
+        // Staging the `deposit` selector in chunks.
+        assert_ok!(Xvm::stage_input(
+            RuntimeOrigin::signed(ALICE),
+            hex::decode("0000").expect("invalid selector hex"),
+        ));
+        assert_ok!(Xvm::stage_input(
+            RuntimeOrigin::signed(ALICE),
+            hex::decode("002a").expect("invalid selector hex"),
+        ));
+
+        let prev_balance = Balances::free_balance(&wasm_payable_addr);
+        let value = UNIT;
+        assert_ok!(Xvm::commit_and_call(
+            RuntimeOrigin::signed(ALICE),
+            wasm_payable_addr.clone().encode(),
+            value,
+            Weight::from_parts(10_000_000_000, 1024 * 1024),
+            None,
+        ));
+        assert_eq!(
+            Balances::free_balance(wasm_payable_addr.clone()),
+            value + prev_balance
+        );
+        // The staged input is consumed.
+        assert!(Xvm::commit_and_call(
+            RuntimeOrigin::signed(ALICE),
+            wasm_payable_addr.encode(),
+            value,
+            Weight::from_parts(10_000_000_000, 1024 * 1024),
+            None,
+        )
+        .is_err());
+    });
+}
+
 #[test]
 fn calling_wasm_payable_from_evm_fails_if_caller_contract_balance_below_ed() {
     new_test_ext().execute_with(|| {
@@ -409,14 +716,61 @@ fn reentrance_not_allowed() {
                 assert!(flags.contains(ReturnFlags::REVERT));
 
                 let reentrance_msg_error = evm_revert_message_error(&format!("{:?}", FailureError::ReentranceDenied));
-                let error_string = String::from_utf8(data).expect("invalid utf8");
-                assert!(error_string.contains(&format!("{:?}", reentrance_msg_error)));
+                let failure = XvmFailure::decode(&mut &data[..]).expect("invalid xvm failure");
+                assert_eq!(
+                    FailureReason::try_from(failure),
+                    Ok(FailureReason::Revert(FailureRevert::VmRevert(reentrance_msg_error)))
+                );
             }
             _ => panic!("unexpected wasm call result"),
         }
     });
 }
 
+#[test]
+fn reentrance_denied_on_evm_wasm_evm_round_trip() {
+    new_test_ext().execute_with(|| {
+        let (code, _) = astar_test_utils::load_wasm_module::<Runtime>(CALL_EVM_PAYBLE_NAME)
+            .expect("invalid wasm module");
+        let wasm_caller_addr = test_utils::deploy_wasm_contract::<Runtime>(
+            ALICE,
+            code,
+            hex::decode("9bae9d5e").expect("invalid data hex"),
+        );
+        let evm_payable_addr = test_utils::deploy_evm_contract::<Runtime>(
+            alith(),
+            hex::decode(EVM_PAYABLE).expect("invalid code hex"),
+        );
+
+        // Call path: EVM -> WASM -> EVM
+        let deposit_func = hex::decode("d0e30db0").expect("invalid deposit function hex");
+        let input = hex::decode("0000002a")
+            .expect("invalid selector hex")
+            .iter()
+            .chain(evm_payable_addr.as_ref().to_vec().encode().iter())
+            .chain(deposit_func.encode().iter())
+            .cloned()
+            .collect::<Vec<_>>();
+        let result = XvmCallBuilder::<Runtime>::wasm(ALICE, wasm_caller_addr)
+            .input(input)
+            .call();
+
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Revert(FailureRevert::VmRevert(data)),
+                ..
+            }) => {
+                let failure = XvmFailure::decode(&mut &data[..]).expect("invalid xvm failure");
+                assert_eq!(
+                    FailureReason::try_from(failure),
+                    Ok(FailureReason::Error(FailureError::ReentranceDenied))
+                );
+            }
+            _ => panic!("unexpected wasm call result: {:?}", result),
+        }
+    });
+}
+
 /*
 
 pragma solidity >=0.8.2 <0.9.0;
@@ -448,6 +802,10 @@ fn evm_call_via_xvm_fails_if_revert() {
             Context {
                 source_vm_id: VmId::Wasm,
                 weight_limit: Weight::from_parts(1_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Evm,
             ALICE,
@@ -471,6 +829,10 @@ fn evm_call_via_xvm_fails_if_revert() {
             Context {
                 source_vm_id: VmId::Wasm,
                 weight_limit: Weight::from_parts(1_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Evm,
             ALICE,
@@ -542,6 +904,10 @@ fn wasm_call_via_xvm_fails_if_revert() {
             Context {
                 source_vm_id: VmId::Evm,
                 weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Wasm,
             ALICE,
@@ -563,6 +929,121 @@ fn wasm_call_via_xvm_fails_if_revert() {
     });
 }
 
+#[test]
+fn wasm_call_via_xvm_keeps_revert_error() {
+    new_test_ext().execute_with(|| {
+        /// Mirror of the `dummy_error` contract error.
+        #[derive(Debug, PartialEq, Eq, Decode)]
+        enum DummyContractError {
+            #[codec(index = 7)]
+            DummyError,
+        }
+
+        let wasm_callee_addr = deploy_wasm_contract(WASM_DUMMY_ERROR_NAME);
+        let result = Xvm::call(
+            Context {
+                source_vm_id: VmId::Evm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            // Calling `do_revert`
+            hex::decode("0000002a").expect("invalid selector hex"),
+            0,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Revert(FailureRevert::VmRevert(data)),
+                ..
+            }) => {
+                // `Ok(Err(Error::DummyError))`, not truncated.
+                assert_eq!(data, vec![0x00, 0x01, 0x07]);
+                assert_eq!(
+                    decode_wasm_revert_error::<DummyContractError>(&data),
+                    Some(DummyContractError::DummyError)
+                );
+            }
+            _ => panic!("unexpected wasm call result: {:?}", result),
+        }
+    });
+}
+
+#[test]
+fn wasm_call_via_xvm_fails_if_trapped() {
+    new_test_ext().execute_with(|| {
+        let wasm_callee_addr = deploy_wasm_contract(WASM_DUMMY_ERROR_NAME);
+        // Unlike a revert, ink! contracts trap on an unknown selector.
+        let input = hex::decode("deadbeef").expect("invalid selector hex");
+        let result = Xvm::call(
+            Context {
+                source_vm_id: VmId::Evm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            input,
+            0,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Error(FailureError::ContractTrapped),
+                ..
+            }) => {}
+            _ => panic!("unexpected wasm call result: {:?}", result),
+        }
+    });
+}
+
+#[test]
+fn wasm_call_via_xvm_rolls_back_value_if_trapped() {
+    new_test_ext().execute_with(|| {
+        let wasm_callee_addr = deploy_wasm_contract(WASM_DUMMY_ERROR_NAME);
+        let prev_alice_balance = Balances::free_balance(&ALICE);
+        let prev_callee_balance = Balances::free_balance(&wasm_callee_addr);
+
+        let result = Xvm::call(
+            Context {
+                source_vm_id: VmId::Evm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            hex::decode("deadbeef").expect("invalid selector hex"),
+            UNIT,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Error(FailureError::ContractTrapped),
+                ..
+            }) => {}
+            _ => panic!("unexpected wasm call result: {:?}", result),
+        }
+        assert_eq!(Balances::free_balance(&ALICE), prev_alice_balance);
+        assert_eq!(
+            Balances::free_balance(&wasm_callee_addr),
+            prev_callee_balance
+        );
+    });
+}
+
 #[test]
 fn evm_caller_reverts_if_wasm_callee_reverted() {
     new_test_ext().execute_with(|| {
@@ -637,8 +1118,8 @@ fn wasm_caller_reverts_if_evm_callee_reverted() {
                 let revert_failure = FailureReason::Revert(FailureRevert::VmRevert(
                     evm_revert_message_error("too shiny"),
                 ));
-                let error_string = String::from_utf8(data).expect("invalid utf8");
-                assert!(error_string.contains(&format!("{:?}", revert_failure)));
+                let failure = XvmFailure::decode(&mut &data[..]).expect("invalid xvm failure");
+                assert_eq!(FailureReason::try_from(failure), Ok(revert_failure));
             }
             _ => panic!("unexpected wasm call result"),
         }
@@ -686,28 +1167,49 @@ const WASM_SIMPLE_STORAGE_NAME: &'static str = "simple_storage";
 fn wasm_call_via_xvm_fails_if_storage_deposit_limit_exhausted() {
     new_test_ext().execute_with(|| {
         let wasm_callee_addr = deploy_wasm_contract(WASM_SIMPLE_STORAGE_NAME);
-        let input = hex::decode("0000002a")
-            .expect("invalid selector hex");
+        let input = hex::decode("0000002a").expect("invalid selector hex");
         let result = Xvm::call(
             Context {
                 source_vm_id: VmId::Evm,
                 weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Wasm,
             ALICE,
             wasm_callee_addr.clone().encode(),
             input,
             0,
-            Some(0)
+            Some(0),
         );
         match result {
             Err(CallFailure {
-                reason: FailureReason::Error(FailureError::VmError(data)),
+                reason: FailureReason::Error(FailureError::OutOfStorageDeposit),
                 ..
-            }) => {
-                let error_string = "WASM call error: Module(ModuleError { index: 70, error: [22, 0, 0, 0], message: Some(\"StorageDepositLimitExhausted\") })";
-                assert_eq!(data, error_string.as_bytes());
-            },
+            }) => {}
+            _ => panic!("unexpected wasm call result"),
+        }
+
+        // No partial writes.
+        let result = Contracts::bare_call(
+            ALICE,
+            wasm_callee_addr,
+            0,
+            Weight::from_parts(10_000_000_000, 1024 * 1024),
+            None,
+            // `get` selector
+            hex::decode("0000002b").expect("invalid selector hex"),
+            DebugInfo::Skip,
+            CollectEvents::Skip,
+            Determinism::Enforced,
+        );
+        match result.result {
+            Ok(ExecReturnValue { flags, data }) => {
+                assert!(!flags.contains(ReturnFlags::REVERT));
+                assert_eq!(data[1], 0);
+            }
             _ => panic!("unexpected wasm call result"),
         }
     });
@@ -722,6 +1224,10 @@ fn wasm_call_via_xvm_call_works_if_sufficient_storage_deposit_limit() {
             Context {
                 source_vm_id: VmId::Evm,
                 weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
             },
             VmId::Wasm,
             ALICE,
@@ -733,6 +1239,33 @@ fn wasm_call_via_xvm_call_works_if_sufficient_storage_deposit_limit() {
     });
 }
 
+#[test]
+fn wasm_call_via_xvm_returns_storage_deposit() {
+    new_test_ext().execute_with(|| {
+        let wasm_callee_addr = deploy_wasm_contract(WASM_SIMPLE_STORAGE_NAME);
+        // `store` selector
+        let input = hex::decode("0000002a").expect("invalid selector hex");
+        let output = Xvm::call(
+            Context {
+                source_vm_id: VmId::Evm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            input,
+            0,
+            Some(UNIT),
+        )
+        .expect("wasm call failed");
+        assert!(output.storage_deposit.expect("no storage deposit") > 0);
+    });
+}
+
 /*
 
 // SPDX-License-Identifier: GPL-3.0
@@ -758,6 +1291,35 @@ contract CallXVMPayble {
  */
 const CALL_XVM_PAYABLE_WITH_SDL: &'static str = "608060405234801561001057600080fd5b50610609806100206000396000f3fe60806040526004361061001e5760003560e01c80632d9338da14610023575b600080fd5b61003d600480360381019061003891906101a6565b610054565b60405161004b9291906102f8565b60405180910390f35b6000606061500573ffffffffffffffffffffffffffffffffffffffff1663acc57c7e601f8a8a8a8a8a8a6040518863ffffffff1660e01b81526004016100a097969594939291906103c5565b6000604051808303816000875af11580156100bf573d6000803e3d6000fd5b505050506040513d6000823e3d601f19601f820116820180604052508101906100e89190610577565b91509150965096945050505050565b6000604051905090565b600080fd5b600080fd5b600080fd5b600080fd5b600080fd5b60008083601f8401126101305761012f61010b565b5b8235905067ffffffffffffffff81111561014d5761014c610110565b5b60208301915083600182028301111561016957610168610115565b5b9250929050565b6000819050919050565b61018381610170565b811461018e57600080fd5b50565b6000813590506101a08161017a565b92915050565b600080600080600080608087890312156101c3576101c2610101565b5b600087013567ffffffffffffffff8111156101e1576101e0610106565b5b6101ed89828a0161011a565b9650965050602087013567ffffffffffffffff8111156102105761020f610106565b5b61021c89828a0161011a565b9450945050604061022f89828a01610191565b925050606061024089828a01610191565b9150509295509295509295565b60008115159050919050565b6102628161024d565b82525050565b600081519050919050565b600082825260208201905092915050565b60005b838110156102a2578082015181840152602081019050610287565b60008484015250505050565b6000601f19601f8301169050919050565b60006102ca82610268565b6102d48185610273565b93506102e4818560208601610284565b6102ed816102ae565b840191505092915050565b600060408201905061030d6000830185610259565b818103602083015261031f81846102bf565b90509392505050565b6000819050919050565b600060ff82169050919050565b6000819050919050565b600061036461035f61035a84610328565b61033f565b610332565b9050919050565b61037481610349565b82525050565b82818337600083830152505050565b60006103958385610273565b93506103a283858461037a565b6103ab836102ae565b840190509392505050565b6103bf81610170565b82525050565b600060a0820190506103da600083018a61036b565b81810360208301526103ed81888a610389565b90508181036040830152610402818688610389565b905061041160608301856103b6565b61041e60808301846103b6565b98975050505050505050565b6104338161024d565b811461043e57600080fd5b50565b6000815190506104508161042a565b92915050565b600080fd5b7f4e487b7100000000000000000000000000000000000000000000000000000000600052604160045260246000fd5b610493826102ae565b810181811067ffffffffffffffff821117156104b2576104b161045b565b5b80604052505050565b60006104c56100f7565b90506104d1828261048a565b919050565b600067ffffffffffffffff8211156104f1576104f061045b565b5b6104fa826102ae565b9050602081019050919050565b600061051a610515846104d6565b6104bb565b90508281526020810184848401111561053657610535610456565b5b610541848285610284565b509392505050565b600082601f83011261055e5761055d61010b565b5b815161056e848260208601610507565b91505092915050565b6000806040838503121561058e5761058d610101565b5b600061059c85828601610441565b925050602083015167ffffffffffffffff8111156105bd576105bc610106565b5b6105c985828601610549565b915050925092905056fea2646970667358221220e44af7386feb3ae682c95df11f7b3de851b515869a073d6fabe05758e94e351f64736f6c63430008120033";
 
+#[test]
+fn wasm_call_via_xvm_works_if_value_covers_storage_deposit() {
+    new_test_ext().execute_with(|| {
+        let wasm_callee_addr = deploy_wasm_contract(WASM_SIMPLE_STORAGE_NAME);
+        let prev_balance = Balances::free_balance(&ALICE);
+        let input = hex::decode("0000002a").expect("invalid selector hex");
+
+        // The whole value is kept for the storage deposit, nothing is transferred to the
+        // non-payable `store`.
+        assert_ok!(Xvm::call(
+            Context {
+                source_vm_id: VmId::Evm,
+                weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: true,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.clone().encode(),
+            input,
+            UNIT,
+            Some(UNIT)
+        ));
+        assert!(Balances::free_balance(&ALICE) >= prev_balance - UNIT);
+    });
+}
+
 #[test]
 fn calling_wasm_from_evm_works_if_sufficient_storage_deposit_limit() {
     new_test_ext().execute_with(|| {
@@ -815,3 +1377,141 @@ fn calling_wasm_from_evm_works_if_sufficient_storage_deposit_limit() {
         }
     });
 }
+
+/* EVM out of gas:
+
+Runtime code loops forever, wrapped in the init code returning it.
+
+```
+JUMPDEST
+PUSH1 0x00
+JUMP
+```
+*/
+const EVM_INFINITE_LOOP: &'static str = "635b6000566000526004601cf3";
+
+#[test]
+fn evm_call_via_xvm_fails_if_out_of_gas() {
+    new_test_ext().execute_with(|| {
+        let evm_callee_addr = deploy_evm_contract(EVM_INFINITE_LOOP);
+
+        let result = Xvm::call(
+            Context {
+                source_vm_id: VmId::Wasm,
+                weight_limit: Weight::from_parts(1_000_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Evm,
+            ALICE,
+            evm_callee_addr.as_ref().to_vec(),
+            vec![],
+            0,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Error(FailureError::OutOfGas),
+                ..
+            }) => {}
+            _ => panic!("unexpected evm call result: {:?}", result),
+        }
+    });
+}
+
+#[test]
+fn wasm_call_via_xvm_fails_if_out_of_gas() {
+    new_test_ext().execute_with(|| {
+        let wasm_callee_addr = deploy_wasm_contract(WASM_SIMPLE_STORAGE_NAME);
+
+        // Too small to run the contract, but enough to enter the VM.
+        let result = Xvm::call(
+            Context {
+                source_vm_id: VmId::Evm,
+                weight_limit: Weight::from_parts(10_000_000, 1024 * 1024),
+                storage_deposit_from_value: false,
+                trace_id: [0; 16],
+                discard_output: false,
+                record_call_graph: false,
+            },
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            // `store` selector
+            hex::decode("0000002a").expect("invalid selector hex"),
+            0,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Error(FailureError::OutOfGas),
+                ..
+            }) => {}
+            _ => panic!("unexpected wasm call result: {:?}", result),
+        }
+    });
+}
+
+#[test]
+fn wasm_call_via_xvm_discards_output_if_requested() {
+    new_test_ext().execute_with(|| {
+        let wasm_callee_addr = deploy_wasm_contract(WASM_SIMPLE_STORAGE_NAME);
+        let context = |discard_output| Context {
+            source_vm_id: VmId::Evm,
+            weight_limit: Weight::from_parts(10_000_000_000, 1024 * 1024),
+            storage_deposit_from_value: false,
+            trace_id: [0; 16],
+            discard_output,
+            record_call_graph: false,
+        };
+        // `get` selector
+        let input = hex::decode("0000002b").expect("invalid selector hex");
+
+        let output = Xvm::call(
+            context(false),
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            input.clone(),
+            0,
+            None,
+        )
+        .expect("call should succeed");
+        assert!(!output.output.is_empty());
+
+        let output = Xvm::call(
+            context(true),
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            input,
+            0,
+            None,
+        )
+        .expect("call should succeed");
+        assert!(output.output.is_empty());
+
+        // Revert data is kept.
+        let wasm_callee_addr = deploy_wasm_contract(WASM_DUMMY_ERROR_NAME);
+        let result = Xvm::call(
+            context(true),
+            VmId::Wasm,
+            ALICE,
+            wasm_callee_addr.encode(),
+            hex::decode("0000002a").expect("invalid selector hex"),
+            0,
+            None,
+        );
+        match result {
+            Err(CallFailure {
+                reason: FailureReason::Revert(FailureRevert::VmRevert(data)),
+                ..
+            }) => {
+                assert_eq!(data.last(), Some(&7u8));
+            }
+            _ => panic!("unexpected wasm call result: {:?}", result),
+        }
+    });
+}